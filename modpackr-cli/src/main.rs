@@ -1,7 +1,12 @@
 use {
 	clap::Parser,
-	modpackr::util::*,
-	std::env::current_dir,
+	modpackr::{Bridge, ManualJar, download::Store, util::*},
+	std::{
+		env::current_dir,
+		io::{self, Write},
+		path::Path,
+		str::FromStr,
+	},
 	util::{ModpackrCli, ModpackrCommand},
 };
 
@@ -10,6 +15,7 @@ mod util;
 fn main() -> anyhow::Result<()> {
 	dotenv::dotenv()?;
 	let cli = ModpackrCli::parse();
+	modpackr::http::set_offline(cli.offline);
 
 	match cli.command {
 		ModpackrCommand::New { name } => {
@@ -35,8 +41,27 @@ fn main() -> anyhow::Result<()> {
 			curseforge,
 			modrinth,
 			manual,
+			hash,
+			redistributable,
 		} => {
-			if let Err(e) = add_mod(&current_dir()?, &mod_name, curseforge, modrinth, manual) {
+			let project_dir = current_dir()?;
+			// The jar comes first, so a bad path or hash doesn't leave the mod half added
+			let jar = manual
+				.clone()
+				.flatten()
+				.map(|source| manual_jar(&project_dir, &mod_name, source, hash, redistributable))
+				.transpose();
+
+			if let Err(e) = jar.and_then(|jar| {
+				add_mod(
+					&project_dir,
+					&mod_name,
+					curseforge,
+					modrinth,
+					manual.is_some(),
+					jar,
+				)
+			}) {
 				eprintln!("Failed to add mod {}: {e}", mod_name);
 				Err(e)
 			} else {
@@ -44,32 +69,205 @@ fn main() -> anyhow::Result<()> {
 				Ok(())
 			}
 		},
+		ModpackrCommand::Edit {
+			mod_name,
+			priority,
+			required,
+			channel,
+			client,
+			server,
+			redistributable,
+		} => {
+			let channel = parse_override(channel)?;
+			let client = parse_override(client)?;
+			let server = parse_override(server)?;
+
+			let result = edit_mod(&current_dir()?, &mod_name, |m| {
+				if let Some(priority) = priority {
+					m.priority = Some(priority);
+				}
+				if let Some(required) = required {
+					m.required = required;
+				}
+				if let Some(channel) = channel {
+					m.channel = channel;
+				}
+				if let Some(client) = client {
+					m.client = client;
+				}
+				if let Some(server) = server {
+					m.server = server;
+				}
+				if let Some(redistributable) = redistributable &&
+					let Some(ref mut jar) = m.jar
+				{
+					jar.redistributable = redistributable;
+				}
+			});
+
+			match result {
+				Ok(m) => {
+					println!(
+						"{}: priority {}, {}, channel {}, client {}, server {}",
+						m.name,
+						m.weight(),
+						if m.required { "required" } else { "optional" },
+						m.channel
+							.map_or("default".to_owned(), |channel| channel.to_string()),
+						m.client
+							.map_or("default".to_owned(), |support| support.to_string()),
+						m.server
+							.map_or("default".to_owned(), |support| support.to_string())
+					);
+					match m.jar {
+						Some(ref jar) if jar.redistributable => {
+							println!("{} is shipped with the pack", jar.file_name)
+						},
+						Some(ref jar) => println!(
+							"{} is not shipped with the pack, players have to install it themselves",
+							jar.file_name
+						),
+						None if redistributable.is_some() => {
+							println!("[WARN] {} has no jar to ship", m.name)
+						},
+						None => {},
+					}
+					Ok(())
+				},
+				Err(e) => {
+					eprintln!("Failed to edit mod {}: {e}", mod_name);
+					Err(e)
+				},
+			}
+		},
+		ModpackrCommand::Pin { mod_name, pin } => {
+			if let Err(e) = pin_mod(&current_dir()?, &mod_name, Some(pin.clone())) {
+				eprintln!("Failed to pin {}: {e}", mod_name);
+				Err(e)
+			} else {
+				println!("Pinned {} to {}", mod_name, pin);
+				Ok(())
+			}
+		},
+		ModpackrCommand::Unpin { mod_name } => {
+			if let Err(e) = pin_mod(&current_dir()?, &mod_name, None) {
+				eprintln!("Failed to unpin {}: {e}", mod_name);
+				Err(e)
+			} else {
+				println!("Unpinned {}", mod_name);
+				Ok(())
+			}
+		},
 		ModpackrCommand::Check => {
-			let (result, manual_mods) = check(&current_dir()?)?;
+			let result = check(&current_dir()?)?;
 
-			if !manual_mods.is_empty() {
+			if !result.manual_mods.is_empty() {
 				println!(
 					"{} mods have no provider and must be manually added to the mods folder",
-					manual_mods.len()
+					result.manual_mods.len()
 				);
-				for m in manual_mods {
+				for m in result.manual_mods {
 					println!("\t{m}")
 				}
 			}
 
-			if let Some((loader, version)) = result {
+			if !result.added_dependencies.is_empty() {
+				println!(
+					"Added {} mods that other mods require",
+					result.added_dependencies.len()
+				);
+				for (m, required_by) in result.added_dependencies {
+					println!("\t{m} (required by {required_by})")
+				}
+			}
+
+			if !result.bridged.is_empty() {
+				println!(
+					"{} mods only work through a loader bridge",
+					result.bridged.len()
+				);
+				for (m, loader) in result.bridged {
+					println!("\t{m} (made for {loader})")
+				}
+			}
+
+			if !result.conflicts.is_empty() {
+				println!(
+					"[WARN] {} pairs of mods in the pack declare they don't work together",
+					result.conflicts.len()
+				);
+				for (m, other) in result.conflicts {
+					println!("\t{m} is incompatible with {other}")
+				}
+			}
+
+			if let Some((loader, version)) = result.target {
 				println!("Found a compatible version!\nLoader: {loader}\nVersion: {version}");
+
+				if result.compatible.len() > 1 {
+					println!("Every compatible version, best first:");
+					for (loader, version) in result.compatible {
+						println!("\t{loader} {version}");
+					}
+				}
 			} else {
 				println!("Failed to find a compatible version");
+				for diagnostic in result.diagnostics {
+					println!("{diagnostic}");
+				}
+			}
+
+			if !result.ranking.is_empty() {
+				println!("Targets by how much of the pack they support:");
+				for target in result.ranking {
+					println!("\t{target}");
+				}
 			}
 			Ok(())
 		},
+		ModpackrCommand::Lock => {
+			if let Err(e) = lock(&current_dir()?) {
+				eprintln!("Failed to lock modpack: {e}");
+				Err(e)
+			} else {
+				println!("Successfully wrote modpack.lock");
+				Ok(())
+			}
+		},
+		ModpackrCommand::Update { mods } => match update(&current_dir()?, &mods) {
+			Ok(lockfile) => {
+				for m in lockfile
+					.mods
+					.iter()
+					.filter(|m| mods.is_empty() || mods.contains(&m.name))
+				{
+					for file in m.files.iter() {
+						println!("{} ({}): {}", m.name, file.provider, file.file_name);
+					}
+				}
+				println!("Successfully updated modpack.lock");
+				Ok(())
+			},
+			Err(e) => {
+				eprintln!("Failed to update modpack.lock: {e}");
+				Err(e)
+			},
+		},
 		ModpackrCommand::Export {
 			curseforge,
 			modrinth,
 			neither,
+			server_pack,
+			side,
 		} => {
-			if let Err(e) = export(&current_dir()?, curseforge, modrinth, neither) {
+			if let Err(e) = export(
+				&current_dir()?,
+				curseforge,
+				modrinth,
+				neither,
+				server_pack,
+				side,
+			) {
 				eprintln!("Failed to export modpack: {e}");
 				Err(e)
 			} else {
@@ -77,5 +275,225 @@ fn main() -> anyhow::Result<()> {
 				Ok(())
 			}
 		},
+		ModpackrCommand::Import { file, dir } => {
+			let file = Path::new(&file);
+			let dir = match dir {
+				Some(dir) => current_dir()?.join(dir),
+				None => current_dir()?.join(
+					file.file_stem()
+						.ok_or(anyhow::anyhow!("{} is not a file", file.display()))?,
+				),
+			};
+
+			match import_archive(file, &dir) {
+				Ok(manual_mods) => {
+					if !manual_mods.is_empty() {
+						println!(
+							"{} mods could not be matched to a provider and were added as manual mods",
+							manual_mods.len()
+						);
+						for m in manual_mods {
+							println!("\t{m}")
+						}
+					}
+
+					println!("Successfully imported modpack into {}", dir.display());
+					Ok(())
+				},
+				Err(e) => {
+					eprintln!("Failed to import modpack: {e}");
+					Err(e)
+				},
+			}
+		},
+		ModpackrCommand::Gc => match Store::open().and_then(|store| store.gc()) {
+			Ok(report) => {
+				println!(
+					"Removed {} unused downloads, freeing {:.1} MB",
+					report.removed,
+					report.freed as f64 / 1_000_000.0
+				);
+				Ok(())
+			},
+			Err(e) => {
+				eprintln!("Failed to clean up downloads: {e}");
+				Err(e)
+			},
+		},
+		ModpackrCommand::Config {
+			export,
+			loader,
+			loader_order,
+			strategy,
+			version,
+			snapshots,
+			channel,
+			bridges,
+			solver,
+			parallel_downloads,
+			include,
+			exclude,
+		} => {
+			let project_dir = current_dir()?;
+			let mut config = load_config(&project_dir)?;
+
+			if export.is_none() &&
+				loader.is_none() &&
+				loader_order.is_none() &&
+				strategy.is_none() &&
+				version.is_none() &&
+				snapshots.is_none() &&
+				channel.is_none() &&
+				bridges.is_none() &&
+				solver.is_none() &&
+				parallel_downloads.is_none() &&
+				include.is_none() &&
+				exclude.is_none()
+			{
+				println!(
+					"Export format: {}\nLoader: {}\nLoader order: {}\nStrategy: {}\nVersion: {}\nAllow snapshots: {}\nChannel: {}\nBridges: {}\nSolver: {}\nParallel downloads: {}\nInclude overrides: {}\nExclude overrides: {}",
+					config.export,
+					config
+						.loader
+						.clone()
+						.map_or("auto".to_owned(), |loader| loader.to_string()),
+					config
+						.loaders()
+						.iter()
+						.map(ToString::to_string)
+						.collect::<Vec<_>>()
+						.join(", "),
+					config.strategy,
+					config.version.unwrap_or("auto".to_owned()),
+					config.allow_snapshots,
+					config.channel,
+					if config.bridges.is_empty() {
+						"none".to_owned()
+					} else {
+						config
+							.bridges
+							.iter()
+							.map(ToString::to_string)
+							.collect::<Vec<_>>()
+							.join(", ")
+					},
+					config.solver,
+					config.parallel_downloads,
+					if config.overrides.include.is_empty() {
+						"everything".to_owned()
+					} else {
+						config.overrides.include.join(", ")
+					},
+					if config.overrides.exclude.is_empty() {
+						"nothing".to_owned()
+					} else {
+						config.overrides.exclude.join(", ")
+					}
+				);
+				return Ok(());
+			}
+
+			if let Some(export) = export {
+				config.export = export;
+			}
+			if let Some(loader) = loader {
+				config.loader = if loader == "auto" {
+					None
+				} else {
+					Some(loader.parse()?)
+				};
+			}
+			if let Some(loader_order) = loader_order {
+				config.loader_order = loader_order;
+			}
+			if let Some(strategy) = strategy {
+				config.strategy = strategy;
+			}
+			if let Some(version) = version {
+				config.version = if version == "auto" {
+					None
+				} else {
+					Some(version)
+				};
+			}
+			if let Some(snapshots) = snapshots {
+				config.allow_snapshots = snapshots;
+			}
+			if let Some(channel) = channel {
+				config.channel = channel;
+			}
+			if let Some(bridges) = bridges {
+				config.bridges = if bridges {
+					Bridge::defaults()
+				} else {
+					Vec::new()
+				};
+			}
+			if let Some(solver) = solver {
+				config.solver = solver;
+			}
+			if let Some(parallel_downloads) = parallel_downloads {
+				config.parallel_downloads = parallel_downloads.max(1);
+			}
+			// `--include ""` clears the list, which comes through as one empty glob
+			if let Some(include) = include {
+				config.overrides.include = include
+					.into_iter()
+					.filter(|glob| !glob.is_empty())
+					.collect();
+			}
+			if let Some(exclude) = exclude {
+				config.overrides.exclude = exclude
+					.into_iter()
+					.filter(|glob| !glob.is_empty())
+					.collect();
+			}
+
+			if let Err(e) = update_config(&project_dir, &config) {
+				eprintln!("Failed to update config: {e}");
+				Err(e)
+			} else {
+				println!("Successfully updated config");
+				Ok(())
+			}
+		},
+	}
+}
+
+/// Copies or downloads a manual mod's jar into the project, and asks whether its license lets the pack ship it unless
+/// `redistributable` already says so
+fn manual_jar(
+	project_dir: &Path,
+	mod_name: &str,
+	source: String,
+	hash: Option<String>,
+	redistributable: bool,
+) -> anyhow::Result<ManualJar> {
+	let mut jar = bundle_jar(project_dir, &JarSource::new(source, hash)?)?;
+
+	jar.redistributable = redistributable ||
+		{
+			print!(
+				"Does the license of {} allow shipping {} in the modpack? Otherwise players have to install it themselves [y/N] ",
+				mod_name, jar.file_name
+			);
+			io::stdout().flush()?;
+
+			let mut answer = String::new();
+			io::stdin().read_line(&mut answer)?;
+			matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+		};
+
+	Ok(jar)
+}
+
+/// Options that override a setting for one mod take `default` to remove the override again
+fn parse_override<T: FromStr<Err = anyhow::Error>>(
+	value: Option<String>,
+) -> anyhow::Result<Option<Option<T>>> {
+	match value.as_deref() {
+		None => Ok(None),
+		Some("default") => Ok(Some(None)),
+		Some(value) => Ok(Some(Some(value.parse()?))),
 	}
 }
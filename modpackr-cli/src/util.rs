@@ -1,10 +1,20 @@
-use clap::{Parser, Subcommand};
+use {
+	clap::{Parser, Subcommand},
+	modpackr::{
+		ExportFormat, ModLoader, Side, Solver, Strategy,
+		provider::{Channel, Pin},
+	},
+};
 
 #[derive(Parser)]
 #[command(name = "modpackr", version, author, about = "Modpack management tool")]
 pub struct ModpackrCli {
 	#[command(subcommand)]
 	pub command: ModpackrCommand,
+
+	/// Only use cached API responses, don't touch the network
+	#[arg(long, global = true)]
+	pub offline: bool,
 }
 
 #[derive(Subcommand)]
@@ -22,10 +32,63 @@ pub enum ModpackrCommand {
 		#[arg(short = 'm', long)]
 		modrinth: Option<String>,
 
-		#[arg(short = 'n', long)]
-		manual: bool,
+		/// A mod that isn't on any provider, optionally with the path or URL of its jar to ship with the pack
+		#[arg(short = 'n', long, value_name = "JAR", num_args = 0..=1)]
+		manual: Option<Option<String>>,
+
+		/// The sha1 or sha512 hash of a jar downloaded with `-n <url>`
+		#[arg(long, requires = "manual")]
+		hash: Option<String>,
+
+		/// Confirms the mod's license allows shipping its jar in the pack, instead of asking
+		#[arg(long, requires = "manual")]
+		redistributable: bool,
+	},
+	/// Changes how check treats a mod
+	Edit {
+		mod_name: String,
+
+		/// How much the mod counts when the coverage solver ranks targets
+		#[arg(short = 'p', long)]
+		priority: Option<u32>,
+
+		/// Whether the coverage solver may suggest targets without this mod
+		#[arg(short = 'r', long)]
+		required: Option<bool>,
+
+		/// The least stable files this mod may use (release, beta or alpha), or `default` to use the pack's channel
+		#[arg(short = 'c', long)]
+		channel: Option<String>,
+
+		/// Whether the mod is required, optional or unsupported on the client, or `default` to go by the providers
+		#[arg(long)]
+		client: Option<String>,
+
+		/// Whether the mod is required, optional or unsupported on the server, or `default` to go by the providers
+		#[arg(long)]
+		server: Option<String>,
+
+		/// Whether a manual mod's license allows shipping its jar in the pack
+		#[arg(long)]
+		redistributable: Option<bool>,
+	},
+	/// Keeps a mod at a version (`0.5.1f`, `0.5.*`) or an exact file (`modrinth:<version id>`,
+	/// `curseforge:<file id>`)
+	Pin {
+		mod_name: String,
+		pin: Pin,
+	},
+	/// Lets a pinned mod move to its newest version again
+	Unpin {
+		mod_name: String,
 	},
 	Check,
+	/// Writes the exact files for every mod to modpack.lock, keeping anything already locked
+	Lock,
+	/// Moves mods in modpack.lock to their newest files, or every mod if none are given
+	Update {
+		mods: Vec<String>,
+	},
 	Export {
 		#[arg(short = 'c', long)]
 		curseforge: bool,
@@ -35,5 +98,70 @@ pub enum ModpackrCommand {
 
 		#[arg(short = 'n', long)]
 		neither: bool,
+
+		/// A server folder and zip with the mods, loader, eula.txt and start scripts
+		#[arg(short = 'S', long)]
+		server_pack: bool,
+
+		/// Only export the mods that run on this side, client or server
+		#[arg(short = 's', long)]
+		side: Option<Side>,
+	},
+	Import {
+		file: String,
+		dir: Option<String>,
+	},
+	/// Shows config.toml, or changes it if any options are given
+	Config {
+		#[arg(short = 'e', long)]
+		export: Option<ExportFormat>,
+
+		/// A loader, or `auto` to let check pick one
+		#[arg(short = 'l', long)]
+		loader: Option<String>,
+
+		/// Loaders in the order check prefers them, comma separated
+		#[arg(short = 'o', long, value_delimiter = ',')]
+		loader_order: Option<Vec<ModLoader>>,
+
+		/// `loader-first` to stick to the preferred loader, or `newest-first` to go for the newest version on any
+		/// loader
+		#[arg(long)]
+		strategy: Option<Strategy>,
+
+		/// A minecraft version, or `auto` to let check pick one
+		#[arg(short = 'v', long = "mc-version")]
+		version: Option<String>,
+
+		/// Whether check may pick snapshots, pre-releases and release candidates
+		#[arg(short = 's', long)]
+		snapshots: Option<bool>,
+
+		/// The least stable files mods may use: release, beta or alpha
+		#[arg(short = 'c', long)]
+		channel: Option<Channel>,
+
+		/// Whether loaders may use mods made for another loader (quilt loading fabric mods, neoforge loading forge
+		/// mods on 1.20.1)
+		#[arg(short = 'b', long)]
+		bridges: Option<bool>,
+
+		/// `strict`, or `coverage` to also rank targets by how much of the pack they support
+		#[arg(long)]
+		solver: Option<Solver>,
+
+		/// How many files are downloaded at once
+		#[arg(short = 'j', long)]
+		parallel_downloads: Option<usize>,
+
+		/// Globs for the override files exports include, comma separated, `""` to include everything
+		#[arg(long, value_delimiter = ',')]
+		include: Option<Vec<String>>,
+
+		/// Globs for the override files exports leave out, comma separated
+		#[arg(long, value_delimiter = ',')]
+		exclude: Option<Vec<String>>,
 	},
+	/// Removes downloaded files that no project's modpack.lock uses anymore
+	Gc,
 }
@@ -2,8 +2,14 @@ use {
 	dotenv::dotenv,
 	eframe::egui::{self, ComboBox},
 	modpackr::{
-		ExportFormat, Modpack,
-		util::{add_mod, check, create_project_at_path, export, load_modpack},
+		Bridge, Config, ExportFormat, ModLoader, Modpack, Side, Solver, Strategy,
+		download::Store,
+		http::set_offline,
+		provider::Channel,
+		util::{
+			JarSource, add_mod, bundle_jar, check, create_project_at_path, export, import_archive,
+			load_config, load_modpack, update, update_config,
+		},
 	},
 	rfd::FileDialog,
 	std::{
@@ -35,6 +41,10 @@ struct MyApp {
 	add_mod_modrinth: String,
 	add_mod_use_curseforge: bool,
 	add_mod_curseforge: String,
+	// Path or URL of a manual mod's jar, the hash is only needed for URLs
+	add_mod_jar: String,
+	add_mod_jar_hash: String,
+	add_mod_redistributable: bool,
 
 	is_checking: bool,
 	check_task: Option<JoinHandle<()>>,
@@ -42,6 +52,19 @@ struct MyApp {
 	is_exporting: bool,
 	export_task: Option<JoinHandle<()>>,
 	export_format: ExportFormat,
+	// Only export the mods that run on this side, everything if it's None
+	export_side: Option<Side>,
+
+	config: Config,
+	config_version: String,
+	// Comma separated, like the CLI takes it
+	config_loader_order: String,
+	// Override globs, comma separated like the loader order
+	config_include: String,
+	config_exclude: String,
+
+	// Only use cached API responses
+	offline: bool,
 
 	screen: Screen,
 }
@@ -53,6 +76,7 @@ enum Screen {
 	Open,
 	Add,
 	Export,
+	Config,
 }
 
 impl eframe::App for MyApp {
@@ -63,43 +87,66 @@ impl eframe::App for MyApp {
 			match self.screen {
 				Screen::None => {
 					ui.heading("No project selected. Open or create a new one");
-					if ui.button("Create New Project").clicked() {
-						if let Some(folder) = FileDialog::new().pick_folder() {
-							let mut log = self.status_log.lock().expect("Error locking status log");
-							match create_project_at_path(&folder) {
-								Ok(_) => {
-									log.push(format!(
-										"Created new project at {}",
-										folder.display()
-									));
-									self.project_path = Some(folder.clone());
-									self.current_project =
-										Some(load_modpack(&folder).expect("Error loading modpack"));
-									self.screen = Screen::Open;
-								},
-								Err(e) => log.push(format!("Failed to create project: {}", e)),
-							}
-							drop(log);
+					if ui.button("Create New Project").clicked() &&
+						let Some(folder) = FileDialog::new().pick_folder()
+					{
+						let mut log = self.status_log.lock().expect("Error locking status log");
+						match create_project_at_path(&folder) {
+							Ok(_) => {
+								log.push(format!("Created new project at {}", folder.display()));
+								self.project_path = Some(folder.clone());
+								self.current_project =
+									Some(load_modpack(&folder).expect("Error loading modpack"));
+								self.screen = Screen::Open;
+							},
+							Err(e) => log.push(format!("Failed to create project: {}", e)),
 						}
+						drop(log);
 					}
-					if ui.button("Open").clicked() {
-						if let Some(folder) = FileDialog::new().pick_folder() {
-							let mut log = self.status_log.lock().expect("Error locking status log");
-							match load_modpack(&folder) {
-								Ok(_) => {
-									log.push(format!("Opened project {}", folder.display()));
-									self.project_path = Some(folder.clone());
-									self.current_project = Some(load_modpack(&folder).unwrap());
-									self.screen = Screen::Open;
-								},
-								Err(e) => log.push(format!("Failed to open project: {}", e)),
-							}
-							drop(log);
+					if ui.button("Open").clicked() &&
+						let Some(folder) = FileDialog::new().pick_folder()
+					{
+						let mut log = self.status_log.lock().expect("Error locking status log");
+						match load_modpack(&folder) {
+							Ok(_) => {
+								log.push(format!("Opened project {}", folder.display()));
+								self.project_path = Some(folder.clone());
+								self.current_project = Some(load_modpack(&folder).unwrap());
+								self.screen = Screen::Open;
+							},
+							Err(e) => log.push(format!("Failed to open project: {}", e)),
 						}
+						drop(log);
 					}
-					if ui.button("Import").clicked() {
+					if ui.button("Import").clicked() &&
+						let Some(file) = FileDialog::new()
+							.add_filter("Modpack", &["modpackr", "mrpack", "zip"])
+							.pick_file() && let Some(folder) = FileDialog::new().pick_folder()
+					{
 						let mut log = self.status_log.lock().expect("Error locking status log");
-						log.push("This is still being worked on...".into());
+						match import_archive(&file, &folder) {
+							Ok(manual_mods) => {
+								log.push(format!(
+									"Imported {} into {}",
+									file.display(),
+									folder.display()
+								));
+								if !manual_mods.is_empty() {
+									log.push(format!(
+										"{} mods could not be matched to a provider and were added as manual mods",
+										manual_mods.len()
+									));
+									for m in manual_mods {
+										log.push(format!("\t{m}"));
+									}
+								}
+								self.project_path = Some(folder.clone());
+								self.current_project =
+									Some(load_modpack(&folder).expect("Error loading modpack"));
+								self.screen = Screen::Open;
+							},
+							Err(e) => log.push(format!("Failed to import project: {}", e)),
+						}
 						drop(log);
 					}
 				},
@@ -108,7 +155,14 @@ impl eframe::App for MyApp {
 					ui.label("Currently included mods:");
 					egui::ScrollArea::vertical().show(ui, |ui| {
 						for line in &self.current_project.as_ref().unwrap().mods {
-							ui.label(&line.name);
+							let mut label = line.name.clone();
+							if let Some(pin) = &line.pin {
+								label.push_str(&format!(" (pinned to {pin})"));
+							}
+							if line.client.is_some() || line.server.is_some() {
+								label.push_str(&format!(" ({})", line.sides(None)));
+							}
+							ui.label(label);
 						}
 					});
 					if ui.button("Add a new mod").clicked() {
@@ -129,23 +183,79 @@ impl eframe::App for MyApp {
 							self.check_task = Some(std::thread::spawn(move || {
 								let mut log = status_log.lock().expect("Error locking status log");
 								match check(&path) {
-									Ok((result, manual_mods)) => {
-										if !manual_mods.is_empty() {
+									Ok(result) => {
+										if !result.manual_mods.is_empty() {
 											log.push(format!(
 												"{} mods have no provider and may require manually inputting them",
-												manual_mods.len()
+												result.manual_mods.len()
 											));
-											for m in manual_mods {
+											for m in result.manual_mods {
 												log.push(format!("\t{m}"));
 											}
 										}
 
-										if let Some((loader, version)) = result {
+										if !result.added_dependencies.is_empty() {
+											log.push(format!(
+												"Added {} mods that other mods require",
+												result.added_dependencies.len()
+											));
+											for (m, required_by) in result.added_dependencies {
+												log.push(format!(
+													"\t{m} (required by {required_by})"
+												));
+											}
+										}
+
+										if !result.bridged.is_empty() {
+											log.push(format!(
+												"{} mods only work through a loader bridge",
+												result.bridged.len()
+											));
+											for (m, loader) in result.bridged {
+												log.push(format!("\t{m} (made for {loader})"));
+											}
+										}
+
+										if !result.conflicts.is_empty() {
+											log.push(format!(
+												"{} pairs of mods in the pack declare they don't work together",
+												result.conflicts.len()
+											));
+											for (m, other) in result.conflicts {
+												log.push(format!(
+													"\t{m} is incompatible with {other}"
+												));
+											}
+										}
+
+										if let Some((loader, version)) = result.target {
 											log.push(format!(
 												"Found a compatible version and loader!\nLoader: {loader}\nVersion: {version}"
 											));
+
+											if result.compatible.len() > 1 {
+												log.push(
+													"Every compatible version, best first:".into(),
+												);
+												for (loader, version) in result.compatible {
+													log.push(format!("\t{loader} {version}"));
+												}
+											}
 										} else {
 											log.push("No compatible version found".into());
+											for diagnostic in result.diagnostics {
+												log.push(diagnostic.to_string());
+											}
+										}
+
+										if !result.ranking.is_empty() {
+											log.push(
+												"Targets by how much of the pack they support:"
+													.into(),
+											);
+											for target in result.ranking {
+												log.push(format!("\t{target}"));
+											}
 										}
 									},
 									Err(e) => log.push(format!("Check failed: {}", e)),
@@ -158,23 +268,54 @@ impl eframe::App for MyApp {
 							egui::Button::new("Check Compatibility (Running...)"),
 						);
 
-						if let Some(handle) = &self.check_task {
-							if handle.is_finished() {
-								let _ = self.check_task.take().unwrap().join();
-								self.is_checking = false;
-							}
+						if let Some(handle) = &self.check_task &&
+							handle.is_finished()
+						{
+							let _ = self.check_task.take().unwrap().join();
+							self.is_checking = false;
 						}
 					}
 
 					if ui.button("Export").clicked() {
+						if let Ok(config) = load_config(self.project_path.as_ref().unwrap()) {
+							self.export_format = config.export;
+						}
 						self.screen = Screen::Export;
 					}
 
+					if ui.button("Settings").clicked() {
+						match load_config(self.project_path.as_ref().unwrap()) {
+							Ok(config) => {
+								self.config_version = config.version.clone().unwrap_or_default();
+								self.config_loader_order = config
+									.loader_order
+									.iter()
+									.map(ToString::to_string)
+									.collect::<Vec<_>>()
+									.join(", ");
+								self.config_include = config.overrides.include.join(", ");
+								self.config_exclude = config.overrides.exclude.join(", ");
+								self.config = config;
+								self.screen = Screen::Config;
+							},
+							Err(e) => {
+								let mut log =
+									self.status_log.lock().expect("Error locking status log");
+								log.push(format!("Failed to load config: {}", e));
+								drop(log);
+							},
+						}
+					}
+
 					if ui.button("Close Project").clicked() {
 						self.screen = Screen::None;
 						self.current_project = None;
 						self.project_path = None;
 					}
+
+					if ui.checkbox(&mut self.offline, "Offline mode").changed() {
+						set_offline(self.offline);
+					}
 				},
 				Screen::Add => {
 					ui.label("Name:");
@@ -188,6 +329,26 @@ impl eframe::App for MyApp {
 					ui.text_edit_singleline(&mut self.add_mod_curseforge);
 					ui.checkbox(&mut self.add_mod_use_curseforge, "Use curseforge?");
 
+					let manual = !self.add_mod_use_modrinth && !self.add_mod_use_curseforge;
+					if manual {
+						ui.label("Jar path or URL (optional):");
+						ui.horizontal(|ui| {
+							ui.text_edit_singleline(&mut self.add_mod_jar);
+							if ui.button("Browse").clicked() &&
+								let Some(path) =
+									FileDialog::new().add_filter("Jar", &["jar"]).pick_file()
+							{
+								self.add_mod_jar = path.display().to_string();
+							}
+						});
+						ui.label("Sha1 or sha512 hash (for URLs):");
+						ui.text_edit_singleline(&mut self.add_mod_jar_hash);
+						ui.checkbox(
+							&mut self.add_mod_redistributable,
+							"The mod's license allows shipping it in the pack",
+						);
+					}
+
 					if ui.button("Add mod").clicked() {
 						let name = self.add_mod_name.trim();
 						let modrinth = if self.add_mod_use_modrinth {
@@ -206,13 +367,22 @@ impl eframe::App for MyApp {
 						if name.is_empty() {
 							log.push("Error: Mod name is required.".into());
 						} else {
-							match add_mod(
-								self.project_path.as_ref().unwrap(),
-								name,
-								curseforge,
-								modrinth,
-								!self.add_mod_use_modrinth && !self.add_mod_use_curseforge,
-							) {
+							let project_path = self.project_path.as_ref().unwrap();
+							let source = self.add_mod_jar.trim();
+							let hash = self.add_mod_jar_hash.trim();
+							let jar = (manual && !source.is_empty())
+								.then(|| {
+									let hash = (!hash.is_empty()).then(|| hash.to_owned());
+									let mut jar =
+										bundle_jar(project_path, &JarSource::new(source, hash)?)?;
+									jar.redistributable = self.add_mod_redistributable;
+									anyhow::Ok(jar)
+								})
+								.transpose();
+
+							match jar.and_then(|jar| {
+								add_mod(project_path, name, curseforge, modrinth, manual, jar)
+							}) {
 								Ok(new_mod) => {
 									log.push(format!("Successfully added {name}!"));
 									self.current_project.as_mut().unwrap().mods.push(new_mod);
@@ -230,6 +400,197 @@ impl eframe::App for MyApp {
 						self.screen = Screen::Open;
 					}
 				},
+				Screen::Config => {
+					ComboBox::from_label("Default Export Format")
+						.selected_text(format!("{:?}", self.config.export))
+						.show_ui(ui, |ui| {
+							ui.selectable_value(
+								&mut self.config.export,
+								ExportFormat::Modrinth,
+								"Modrinth (.mrpack)",
+							);
+							ui.selectable_value(
+								&mut self.config.export,
+								ExportFormat::Curseforge,
+								"CurseForge",
+							);
+							ui.selectable_value(
+								&mut self.config.export,
+								ExportFormat::Modpackr,
+								"Modpackr",
+							);
+							ui.selectable_value(
+								&mut self.config.export,
+								ExportFormat::Server,
+								"Server pack",
+							);
+						});
+
+					ComboBox::from_label("Loader")
+						.selected_text(
+							self.config
+								.loader
+								.as_ref()
+								.map_or("Auto".to_owned(), |loader| format!("{:?}", loader)),
+						)
+						.show_ui(ui, |ui| {
+							ui.selectable_value(&mut self.config.loader, None, "Auto");
+							for loader in [
+								ModLoader::Fabric,
+								ModLoader::Quilt,
+								ModLoader::Forge,
+								ModLoader::Neoforge,
+							] {
+								let label = format!("{:?}", loader);
+								ui.selectable_value(&mut self.config.loader, Some(loader), label);
+							}
+						});
+
+					ui.label("Loader order (comma separated, leave empty for the default):");
+					ui.text_edit_singleline(&mut self.config_loader_order);
+
+					ComboBox::from_label("Strategy")
+						.selected_text(format!("{:?}", self.config.strategy))
+						.show_ui(ui, |ui| {
+							ui.selectable_value(
+								&mut self.config.strategy,
+								Strategy::LoaderFirst,
+								"Preferred loader first",
+							);
+							ui.selectable_value(
+								&mut self.config.strategy,
+								Strategy::NewestFirst,
+								"Newest version first",
+							);
+						});
+
+					ui.label("Minecraft Version (leave empty to pick automatically):");
+					ui.text_edit_singleline(&mut self.config_version);
+
+					ui.checkbox(
+						&mut self.config.allow_snapshots,
+						"Allow snapshots, pre-releases and release candidates",
+					);
+
+					ComboBox::from_label("Channel")
+						.selected_text(format!("{:?}", self.config.channel))
+						.show_ui(ui, |ui| {
+							ui.selectable_value(
+								&mut self.config.channel,
+								Channel::Release,
+								"Release only",
+							);
+							ui.selectable_value(
+								&mut self.config.channel,
+								Channel::Beta,
+								"Allow betas",
+							);
+							ui.selectable_value(
+								&mut self.config.channel,
+								Channel::Alpha,
+								"Allow alphas",
+							);
+						});
+
+					let mut bridges = !self.config.bridges.is_empty();
+					if ui
+						.checkbox(
+							&mut bridges,
+							"Load fabric mods on quilt and forge mods on neoforge 1.20.1",
+						)
+						.changed()
+					{
+						self.config.bridges = if bridges {
+							Bridge::defaults()
+						} else {
+							Vec::new()
+						};
+					}
+
+					ComboBox::from_label("Solver")
+						.selected_text(format!("{:?}", self.config.solver))
+						.show_ui(ui, |ui| {
+							ui.selectable_value(&mut self.config.solver, Solver::Strict, "Strict");
+							ui.selectable_value(
+								&mut self.config.solver,
+								Solver::Coverage,
+								"Coverage (rank targets by supported mods)",
+							);
+						});
+
+					ui.horizontal(|ui| {
+						ui.label("Parallel downloads");
+						ui.add(
+							egui::DragValue::new(&mut self.config.parallel_downloads).range(1..=32),
+						);
+					});
+
+					ui.label(
+						"Override files to export (comma separated globs, leave empty for all):",
+					);
+					ui.text_edit_singleline(&mut self.config_include);
+					ui.label("Override files to leave out (comma separated globs):");
+					ui.text_edit_singleline(&mut self.config_exclude);
+
+					if ui.button("Save").clicked() {
+						let version = self.config_version.trim();
+						self.config.version = if version.is_empty() {
+							None
+						} else {
+							Some(version.to_owned())
+						};
+
+						let globs = |text: &str| {
+							text.split(',')
+								.map(str::trim)
+								.filter(|glob| !glob.is_empty())
+								.map(str::to_owned)
+								.collect()
+						};
+						self.config.overrides.include = globs(&self.config_include);
+						self.config.overrides.exclude = globs(&self.config_exclude);
+
+						let mut log = self.status_log.lock().expect("Error locking status log");
+						let loader_order = self
+							.config_loader_order
+							.split(',')
+							.map(str::trim)
+							.filter(|loader| !loader.is_empty())
+							.map(str::parse)
+							.collect::<anyhow::Result<Vec<ModLoader>>>();
+						match loader_order.and_then(|loader_order| {
+							self.config.loader_order = loader_order;
+							update_config(self.project_path.as_ref().unwrap(), &self.config)
+						}) {
+							Ok(_) => {
+								log.push("Saved settings".into());
+								self.screen = Screen::Open;
+							},
+							Err(e) => log.push(format!("Failed to save settings: {}", e)),
+						}
+						drop(log);
+					}
+
+					// The store is shared by every project, so this doesn't need saving
+					if ui.button("Clean up downloaded files").clicked() {
+						let mut log = self.status_log.lock().expect("Error locking status log");
+						match Store::open().and_then(|store| store.gc()) {
+							Ok(report) => log.push(format!(
+								"Removed {} unused downloads ({:.1} MB)",
+								report.removed,
+								report.freed as f64 / 1_000_000.0
+							)),
+							Err(e) => log.push(format!("Failed to clean up downloads: {}", e)),
+						}
+						drop(log);
+					}
+
+					ui.separator();
+
+					if ui.button("Return (Will NOT save)").clicked() {
+						self.screen = Screen::Open;
+					}
+				},
 				Screen::Export => {
 					ComboBox::from_label("Export Format")
 						.selected_text(format!("{:?}", self.export_format))
@@ -249,6 +610,30 @@ impl eframe::App for MyApp {
 								ExportFormat::Modpackr,
 								"Modpackr",
 							);
+							ui.selectable_value(
+								&mut self.export_format,
+								ExportFormat::Server,
+								"Server pack",
+							);
+						});
+
+					ComboBox::from_label("Side")
+						.selected_text(
+							self.export_side
+								.map_or("Both".to_owned(), |side| format!("{side:?}")),
+						)
+						.show_ui(ui, |ui| {
+							ui.selectable_value(&mut self.export_side, None, "Both");
+							ui.selectable_value(
+								&mut self.export_side,
+								Some(Side::Client),
+								"Client",
+							);
+							ui.selectable_value(
+								&mut self.export_side,
+								Some(Side::Server),
+								"Server",
+							);
 						});
 
 					if !self.is_exporting {
@@ -262,6 +647,7 @@ impl eframe::App for MyApp {
 							let status_log = self.status_log.clone();
 							let path = Arc::new(self.project_path.clone().unwrap());
 							let export_format = self.export_format.clone();
+							let export_side = self.export_side;
 
 							self.export_task = Some(std::thread::spawn(move || {
 								let mut log = status_log.lock().expect("Error locking status log");
@@ -270,6 +656,8 @@ impl eframe::App for MyApp {
 									export_format == ExportFormat::Curseforge,
 									export_format == ExportFormat::Modrinth,
 									export_format == ExportFormat::Modpackr,
+									export_format == ExportFormat::Server,
+									export_side,
 								) {
 									log.push(format!("Failed to export modpack: {e}"));
 								} else {
@@ -278,14 +666,32 @@ impl eframe::App for MyApp {
 								drop(log);
 							}));
 						}
+
+						// Exports reuse whatever is in modpack.lock, this is how newer files get picked up
+						if ui.button("Update Locked Mods").clicked() {
+							self.is_exporting = true;
+
+							let status_log = self.status_log.clone();
+							let path = Arc::new(self.project_path.clone().unwrap());
+
+							self.export_task = Some(std::thread::spawn(move || {
+								let mut log = status_log.lock().expect("Error locking status log");
+								if let Err(e) = update(&path, &[]) {
+									log.push(format!("Failed to update modpack.lock: {e}"));
+								} else {
+									log.push("Successfully updated modpack.lock".into());
+								}
+								drop(log);
+							}));
+						}
 					} else {
 						ui.add_enabled(false, egui::Button::new("Exporting..."));
 
-						if let Some(handle) = &self.export_task {
-							if handle.is_finished() {
-								let _ = self.export_task.take().unwrap().join();
-								self.is_exporting = false;
-							}
+						if let Some(handle) = &self.export_task &&
+							handle.is_finished()
+						{
+							let _ = self.export_task.take().unwrap().join();
+							self.is_exporting = false;
 						}
 					}
 
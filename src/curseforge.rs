@@ -1,12 +1,24 @@
 use {
-	crate::{ModLoader, ModVersions},
+	crate::{
+		ModLoader, ModVersions, Sides, Support,
+		http::HttpClient,
+		provider::{
+			Channel, Dependency, DependencyKind, FileFilter, ModProvider, NoFile, ProviderRef,
+			ResolvedFile,
+		},
+		version::MinecraftVersion,
+	},
 	anyhow::anyhow,
 	chrono::{DateTime, Utc},
-	reqwest::blocking::Client,
 	serde::{Deserialize, Serialize},
-	std::{collections::BTreeSet, time::Duration},
+	std::time::Duration,
 };
-const API_BASE: &str = "https://api.curseforge.com/v1";
+pub const API_BASE: &str = "https://api.curseforge.com/v1";
+
+pub const PROVIDER: &str = "curseforge";
+
+/// The most files curseforge sends in one page
+const PAGE_SIZE: usize = 50;
 
 pub fn get_api_key() -> anyhow::Result<String> {
 	Ok(std::env::var("CURSEFORGE_API_KEY")?)
@@ -20,6 +32,17 @@ pub struct CurseforgeMod {
 	pub slug: String,
 }
 
+impl From<CurseforgeMod> for ProviderRef {
+	fn from(value: CurseforgeMod) -> Self {
+		Self {
+			provider: PROVIDER.to_owned(),
+			id: value.id.to_string(),
+			slug: value.slug,
+			title: value.name,
+		}
+	}
+}
+
 #[derive(Debug, Deserialize)]
 struct GetModResponse {
 	data: CurseforgeMod,
@@ -31,28 +54,28 @@ struct GetSearchResponse {
 }
 pub struct CurseforgeClient {
 	api_key: String,
-	client: Client,
+	api_base: String,
+	http: HttpClient,
 }
 
 impl CurseforgeClient {
 	pub fn new<T: Into<String>>(api_key: T) -> Self {
+		Self::with_base_url(api_key, API_BASE)
+	}
+
+	/// Creates a client that talks to a mirror or mock of the curseforge API instead of the real one
+	pub fn with_base_url<T: Into<String>, U: Into<String>>(api_key: T, api_base: U) -> Self {
 		Self {
 			api_key: api_key.into(),
-			client: Client::new(),
+			api_base: api_base.into().trim_end_matches('/').to_owned(),
+			// Curseforge rate limits aggressively, but cached responses don't count against that
+			http: HttpClient::new().with_rate_limit(Duration::from_secs(1)),
 		}
 	}
 
 	pub fn get_mod(&self, mod_id: u32) -> anyhow::Result<CurseforgeMod> {
-		std::thread::sleep(Duration::from_secs(1));
-		let url = format!("{API_BASE}/mods/{}", mod_id);
-		let res = self
-			.client
-			.get(&url)
-			.header("x-api-key", &self.api_key)
-			.send()?
-			.error_for_status()?;
-
-		let response: GetModResponse = res.json()?;
+		let url = format!("{}/mods/{}", self.api_base, mod_id);
+		let response: GetModResponse = self.http.get_json(&url, &[("x-api-key", &self.api_key)])?;
 		Ok(response.data)
 	}
 
@@ -61,7 +84,7 @@ impl CurseforgeClient {
 		let slug = url
 			.trim_end_matches('/')
 			.split('/')
-			.last()
+			.next_back()
 			.map(str::to_string)
 			.ok_or(anyhow!("Error parsing url"))?;
 
@@ -82,156 +105,214 @@ impl CurseforgeClient {
 	}
 
 	pub fn search_mod<T: Into<String>>(&self, search: T) -> anyhow::Result<Vec<CurseforgeMod>> {
-		std::thread::sleep(Duration::from_secs(1));
 		let url = format!(
 			"{}/mods/search?gameId=432&searchFilter={}",
-			API_BASE,
+			self.api_base,
 			search.into()
 		);
 
-		let response = self
-			.client
-			.get(&url)
-			.header("x-api-key", &self.api_key)
-			.send()?
-			.error_for_status()?;
-
-		let search_response: GetSearchResponse = response.json()?;
+		let search_response: GetSearchResponse =
+			self.http.get_json(&url, &[("x-api-key", &self.api_key)])?;
 
 		Ok(search_response.data)
 	}
 
 	pub fn search_slug<T: Into<String>>(&self, slug: T) -> anyhow::Result<Vec<CurseforgeMod>> {
-		std::thread::sleep(Duration::from_secs(1));
-		let url = format!("{}/mods/search?gameId=432&slug={}", API_BASE, slug.into());
-
-		let response = self
-			.client
-			.get(&url)
-			.header("x-api-key", &self.api_key)
-			.send()?
-			.error_for_status()?;
+		let url = format!(
+			"{}/mods/search?gameId=432&slug={}",
+			self.api_base,
+			slug.into()
+		);
 
-		let search_response: GetSearchResponse = response.json()?;
+		let search_response: GetSearchResponse =
+			self.http.get_json(&url, &[("x-api-key", &self.api_key)])?;
 
 		Ok(search_response.data)
 	}
 
-	pub fn get_versions(&self, id: u32) -> anyhow::Result<ModVersions> {
-		std::thread::sleep(Duration::from_secs(1));
-		let url = format!("{}/mods/{}/files", API_BASE, id);
-		let resp = self
-			.client
-			.get(&url)
-			.header("x-api-key", &self.api_key)
-			.send()?
-			.error_for_status()?;
-
-		let json: serde_json::Value = resp.json()?;
-		let new = Vec::new();
-		let files = json["data"].as_array().unwrap_or(&new);
-
-		let mut fabric = BTreeSet::new();
-		let mut forge = BTreeSet::new();
-		let mut neo_forge = BTreeSet::new();
-		let mut quilt = BTreeSet::new();
-
-		for file in files {
-			let game_versions = file["gameVersions"]
-				.as_array()
-				.unwrap_or(&vec![])
+	/// The newest file `filter` allows for the loader and minecraft version
+	pub fn latest_allowed(
+		&self,
+		id: u32,
+		filter: &FileFilter,
+		loader: &ModLoader,
+		mc_version: &str,
+	) -> anyhow::Result<CurseforgeModFile> {
+		let files = self.get_mod_files(id)?;
+
+		// gameVersions holds both the loader names and the minecraft versions
+		let loader_name = loader.to_string();
+		let has_game_version = |file: &CurseforgeModFile, name: &str| {
+			file.game_versions
 				.iter()
-				.filter_map(|v| v.as_str())
-				.map(|s| s.to_lowercase())
-				.collect::<Vec<_>>();
+				.any(|version| version.eq_ignore_ascii_case(name))
+		};
+
+		let mut files = files
+			.into_iter()
+			.filter(|file| {
+				file.is_allowed(filter) &&
+					has_game_version(file, &loader_name) &&
+					has_game_version(file, mc_version)
+			})
+			.collect::<Vec<_>>();
 
-			let is_fabric = game_versions.iter().any(|v| v == "fabric");
-			let is_forge = game_versions.iter().any(|v| v == "forge");
-			let is_neoforge = game_versions.iter().any(|v| v == "neoforge");
-			let is_quilt = game_versions.iter().any(|v| v == "quilt");
+		files.sort_by_key(|file| std::cmp::Reverse(file.file_date));
 
-			let mc_versions = game_versions
+		Ok(files.into_iter().next().ok_or_else(|| {
+			NoFile(format!(
+				"No file {} found for {} with loader {} on {}",
+				filter, id, loader, mc_version
+			))
+		})?)
+	}
+
+	/// Every loader and minecraft version with a file `filter` allows
+	pub fn get_allowed_versions(
+		&self,
+		id: u32,
+		filter: &FileFilter,
+	) -> anyhow::Result<ModVersions> {
+		let mut versions = ModVersions::default();
+
+		for file in self
+			.get_mod_files(id)?
+			.into_iter()
+			.filter(|file| file.is_allowed(filter))
+		{
+			// Minecraft versions are the game versions that start with a digit, the rest are loaders and sides
+			let mc_versions = file
+				.game_versions
 				.iter()
-				.filter(|v| {
-					v.chars()
-						.next()
-						.map(|c| c.is_ascii_digit())
-						.unwrap_or(false)
-				})
-				.cloned()
+				.filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+				.map(|v| MinecraftVersion::new(v.as_str()))
 				.collect::<Vec<_>>();
 
-			if is_fabric {
-				fabric.extend(mc_versions.iter().cloned());
-			}
-			if is_forge {
-				forge.extend(mc_versions.iter().cloned());
-			}
-			if is_neoforge {
-				neo_forge.extend(mc_versions.iter().cloned());
-			}
-			if is_quilt {
-				quilt.extend(mc_versions.iter().cloned());
+			for loader in file.game_versions.iter().filter_map(|v| v.parse().ok()) {
+				versions
+					.get_mut(&loader)
+					.extend(mc_versions.iter().cloned());
 			}
 		}
 
-		Ok(ModVersions {
-			fabric: fabric.into_iter().collect(),
-			forge: forge.into_iter().collect(),
-			neo_forge: neo_forge.into_iter().collect(),
-			quilt: quilt.into_iter().collect(),
-		})
+		Ok(versions)
 	}
 
-	pub fn latest_stable(
-		&self,
-		id: u32,
-		loader: ModLoader,
-	) -> anyhow::Result<CurseforgeManifestFile> {
-		let files = self.get_mod_files(id)?;
+	/// Every file of the mod. Curseforge hands them out a page at a time, so this keeps asking until it has them all
+	pub fn get_mod_files(&self, id: u32) -> anyhow::Result<Vec<CurseforgeModFile>> {
+		let mut files = Vec::new();
 
-		let loader_name = match loader {
-			ModLoader::Fabric => "Fabric",
-			ModLoader::Quilt => "Quilt",
-			ModLoader::Forge => "Forge",
-			ModLoader::Neoforge => "Neoforge",
+		loop {
+			let url = format!(
+				"{}/mods/{}/files?index={}&pageSize={}",
+				self.api_base,
+				id,
+				files.len(),
+				PAGE_SIZE
+			);
+			let response: CurseforgeModFilesResponse =
+				self.http.get_json(&url, &[("x-api-key", &self.api_key)])?;
+
+			let page_len = response.data.len();
+			files.extend(response.data);
+
+			// Without pagination there's only the one page
+			match response.pagination {
+				Some(pagination) if page_len > 0 && files.len() < pagination.total_count => {},
+				_ => return Ok(files),
+			}
 		}
-		.to_owned();
+	}
+}
 
-		let mut stable_files = files
-			.into_iter()
-			.filter(|file| file.release_type <= 2 && file.game_versions.contains(&loader_name))
-			.collect::<Vec<_>>();
+impl ModProvider for CurseforgeClient {
+	fn name(&self) -> &'static str {
+		PROVIDER
+	}
 
-		stable_files.sort_by(|a, b| b.file_date.cmp(&a.file_date));
+	fn resolve(&self, url_or_slug: &str) -> anyhow::Result<ProviderRef> {
+		Ok(self.from_url(url_or_slug)?.into())
+	}
 
-		stable_files
-			.first()
-			.map(|file| CurseforgeManifestFile {
-				project_id: id,
-				file_id: file.id,
-			})
-			.ok_or(anyhow!(
-				"No stable version found for {} with loader {}",
-				id,
-				loader
-			))
+	fn project(&self, id: &str) -> anyhow::Result<ProviderRef> {
+		Ok(self.get_mod(id.parse()?)?.into())
 	}
 
-	pub fn get_mod_files(&self, id: u32) -> anyhow::Result<Vec<CurseforgeModFile>> {
-		std::thread::sleep(Duration::from_secs(1));
-		let url = format!("{API_BASE}/mods/{}/files", id);
+	fn versions(&self, project: &ProviderRef, filter: &FileFilter) -> anyhow::Result<ModVersions> {
+		self.get_allowed_versions(project.id.parse()?, filter)
+	}
+
+	fn select_file(
+		&self,
+		project: &ProviderRef,
+		loader: &ModLoader,
+		mc_version: &str,
+		filter: &FileFilter,
+	) -> anyhow::Result<ResolvedFile> {
+		let file = self.latest_allowed(project.id.parse()?, filter, loader, mc_version)?;
+		let sides = file.sides();
+
+		let hashes = file
+			.hashes
+			.iter()
+			.filter_map(|hash| {
+				let algorithm = match hash.algo {
+					1 => "sha1",
+					2 => "md5",
+					_ => return None,
+				};
+				Some((algorithm.to_owned(), hash.value.clone()))
+			})
+			.chain(
+				(file.file_fingerprint != 0)
+					.then(|| ("fingerprint".to_owned(), file.file_fingerprint.to_string())),
+			)
+			.collect();
+
+		Ok(ResolvedFile {
+			provider: PROVIDER.to_owned(),
+			project_id: project.id.clone(),
+			file_id: file.id.to_string(),
+			file_name: file.file_name,
+			hashes,
+			url: file.download_url,
+			size: file.file_length,
+			dependencies: file
+				.dependencies
+				.iter()
+				.map(|dependency| Dependency {
+					project_id: dependency.mod_id.to_string(),
+					kind: match dependency.relation_type {
+						3 => DependencyKind::Required,
+						5 => DependencyKind::Incompatible,
+						1 | 6 => DependencyKind::Embedded,
+						_ => DependencyKind::Optional,
+					},
+				})
+				.collect(),
+			bridged_from: None,
+			sides,
+		})
+	}
 
-		let response = self
-			.client
-			.get(&url)
-			.header("x-api-key", self.api_key.clone())
-			.send()?
-			.error_for_status()?;
+	/// Files locked before sides were tracked don't have them, so the tags are looked up again
+	fn sides(&self, project: &ProviderRef, file: &ResolvedFile) -> anyhow::Result<Option<Sides>> {
+		if file.sides.is_some() {
+			return Ok(file.sides);
+		}
 
-		let mod_files_response: CurseforgeModFilesResponse = response.json()?;
+		Ok(self
+			.get_mod_files(project.id.parse()?)?
+			.iter()
+			.find(|mod_file| mod_file.id.to_string() == file.file_id)
+			.and_then(CurseforgeModFile::sides))
+	}
 
-		Ok(mod_files_response.data)
+	fn project_url(&self, project: &ProviderRef) -> String {
+		format!(
+			"https://www.curseforge.com/minecraft/mc-mods/{}",
+			project.slug
+		)
 	}
 }
 
@@ -240,20 +321,85 @@ pub struct CurseforgeModFile {
 	pub id: u32,
 	#[serde(rename = "fileName")]
 	pub file_name: String,
+	#[serde(rename = "displayName", default)]
+	pub display_name: String,
 	#[serde(rename = "releaseType")]
 	pub release_type: u8, // 1=release, 2=beta, 3=alpha
 	#[serde(rename = "fileDate")]
 	pub file_date: DateTime<Utc>,
 	#[serde(rename = "gameVersions")]
 	pub game_versions: Vec<String>,
+	#[serde(default)]
+	pub hashes: Vec<CurseforgeFileHash>,
+	#[serde(rename = "downloadUrl")]
+	pub download_url: Option<String>,
+	#[serde(rename = "fileLength", default)]
+	pub file_length: u64,
+	// See [`fingerprint`], 0 if curseforge didn't send one
+	#[serde(rename = "fileFingerprint", default)]
+	pub file_fingerprint: u32,
+	#[serde(default)]
+	pub dependencies: Vec<CurseforgeFileDependency>,
+}
+
+impl CurseforgeModFile {
+	pub fn channel(&self) -> Channel {
+		match self.release_type {
+			1 => Channel::Release,
+			2 => Channel::Beta,
+			_ => Channel::Alpha,
+		}
+	}
+
+	/// Curseforge tags files with `Client` and `Server` in gameVersions. Plenty of mods that run on both sides are
+	/// only tagged `Client`, so the tags only count for files tagged `Server` alone
+	pub fn sides(&self) -> Option<Sides> {
+		let tagged = |side| self.game_versions.iter().any(|v| v == side);
+
+		(tagged("Server") && !tagged("Client")).then_some(Sides {
+			client: Support::Unsupported,
+			server: Support::Required,
+		})
+	}
+
+	pub fn is_allowed(&self, filter: &FileFilter) -> bool {
+		filter.allows(
+			PROVIDER,
+			&self.id.to_string(),
+			&[&self.file_name, &self.display_name],
+			&self.game_versions,
+			self.channel(),
+		)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurseforgeFileDependency {
+	#[serde(rename = "modId")]
+	pub mod_id: u32,
+	#[serde(rename = "relationType")]
+	pub relation_type: u8, // 1=embedded library, 2=optional, 3=required, 4=tool, 5=incompatible, 6=include
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurseforgeFileHash {
+	pub value: String,
+	pub algo: u8, // 1=sha1, 2=md5
 }
 
 #[derive(Debug, Deserialize)]
 struct CurseforgeModFilesResponse {
 	pub data: Vec<CurseforgeModFile>,
+	pub pagination: Option<CurseforgePagination>,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Deserialize)]
+struct CurseforgePagination {
+	#[serde(rename = "totalCount")]
+	pub total_count: usize,
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct CurseforgeManifest {
 	pub minecraft: CurseforgeMinecraftManifest,
 	#[serde(rename = "manifestType")]
@@ -267,7 +413,7 @@ pub struct CurseforgeManifest {
 	pub overrides: String,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct CurseforgeManifestFile {
 	#[serde(rename = "projectID")]
 	pub project_id: u32,
@@ -275,15 +421,50 @@ pub struct CurseforgeManifestFile {
 	pub file_id: u32,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct CurseforgeMinecraftManifest {
 	pub version: String,
 	#[serde(rename = "modLoaders")]
 	pub mod_loaders: Vec<CurseforgeModLoaderEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct CurseforgeModLoaderEntry {
 	pub id: String,
 	pub primary: bool,
 }
+
+/// Curseforge's file fingerprint, a 32 bit murmur2 hash (seed 1) of the file with every tab, newline, carriage return
+/// and space taken out
+pub fn fingerprint(contents: &[u8]) -> u32 {
+	const M: u32 = 0x5bd1e995;
+
+	let data = contents
+		.iter()
+		.copied()
+		.filter(|byte| !matches!(byte, b'\t' | b'\n' | b'\r' | b' '))
+		.collect::<Vec<_>>();
+
+	let mut hash = 1 ^ data.len() as u32;
+	let mut chunks = data.chunks_exact(4);
+	for chunk in &mut chunks {
+		let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+		k = k.wrapping_mul(M);
+		k ^= k >> 24;
+		k = k.wrapping_mul(M);
+
+		hash = hash.wrapping_mul(M) ^ k;
+	}
+
+	let rest = chunks.remainder();
+	if !rest.is_empty() {
+		for (i, byte) in rest.iter().enumerate() {
+			hash ^= (*byte as u32) << (8 * i);
+		}
+		hash = hash.wrapping_mul(M);
+	}
+
+	hash ^= hash >> 13;
+	hash = hash.wrapping_mul(M);
+	hash ^ (hash >> 15)
+}
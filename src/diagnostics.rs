@@ -0,0 +1,211 @@
+use {
+	crate::{Mod, ModLoader, ModVersions, mojang::VersionCatalogue, version::MinecraftVersion},
+	std::{
+		collections::{BTreeMap, BTreeSet},
+		fmt::Display,
+	},
+};
+
+/// How many of the best versions are shown for each loader
+pub const CANDIDATES_PER_LOADER: usize = 3;
+
+/// How many targets the coverage solver lists
+pub const RANKED_TARGETS: usize = 10;
+
+/// Why no version works on a loader, worked out from the versions every mod supports
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoaderDiagnostic {
+	pub loader: ModLoader,
+	// The versions the fewest mods block, newest first among equals
+	pub candidates: Vec<Candidate>,
+	// The newest version any mod supports on this loader, its blockers are the fewest mods to remove for it to work
+	pub newest: Option<Candidate>,
+}
+
+impl Display for LoaderDiagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "{}:", self.loader)?;
+
+		for candidate in self.candidates.iter() {
+			writeln!(
+				f,
+				"\t{} is blocked by {}",
+				candidate.version,
+				candidate.blockers.join(", ")
+			)?;
+		}
+
+		if let Some(newest) = &self.newest &&
+			!newest.blockers.is_empty()
+		{
+			write!(
+				f,
+				"\tRemoving {} would make the newest version, {}, work",
+				newest.blockers.join(", "),
+				newest.version
+			)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+	pub version: String,
+	// Mods without a file for this loader and version
+	pub blockers: Vec<String>,
+}
+
+/// Explains, for every loader in `loaders`, which mods stand in the way of each version, in the order of `loaders`.
+/// `versions` is keyed by mod name, the same data `check` writes to mods/*.ron. `catalogue` decides what's a release
+/// and which version is newer
+pub fn diagnose(
+	versions: &BTreeMap<String, ModVersions>,
+	catalogue: &VersionCatalogue,
+	loaders: &[ModLoader],
+	force_version: Option<&str>,
+	allow_snapshots: bool,
+) -> Vec<LoaderDiagnostic> {
+	loaders
+		.iter()
+		.filter_map(|loader| {
+			// Nothing at all is published for this loader, so there's nothing useful to say about it
+			if versions
+				.values()
+				.all(|mod_versions| mod_versions.get(loader).is_empty())
+			{
+				return None;
+			}
+
+			let mut supported = match force_version {
+				Some(force_version) => vec![MinecraftVersion::new(force_version)],
+				None => versions
+					.values()
+					.flat_map(|mod_versions| mod_versions.get(loader).iter().cloned())
+					.filter(|version| allow_snapshots || catalogue.is_release(version.as_str()))
+					.collect::<BTreeSet<_>>()
+					.into_iter()
+					.collect(),
+			};
+			supported.sort_by(|a, b| catalogue.compare(a, b));
+
+			let candidate = |version: &MinecraftVersion| Candidate {
+				version: version.to_string(),
+				blockers: versions
+					.iter()
+					.filter(|(_, mod_versions)| !mod_versions.get(loader).contains(version))
+					.map(|(name, _)| name.clone())
+					.collect(),
+			};
+
+			let mut candidates = supported.iter().rev().map(candidate).collect::<Vec<_>>();
+			// Stable, so versions with the same number of blockers stay newest first
+			candidates.sort_by_key(|candidate| candidate.blockers.len());
+			candidates.truncate(CANDIDATES_PER_LOADER);
+
+			Some(LoaderDiagnostic {
+				loader: loader.clone(),
+				candidates,
+				newest: supported.last().map(candidate),
+			})
+		})
+		.collect()
+}
+
+/// A loader and minecraft version scored by the coverage solver
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedTarget {
+	pub loader: ModLoader,
+	pub version: String,
+	// Sum of the weights of the mods that support this target
+	pub score: u32,
+	// How many mods support it
+	pub supported: usize,
+	// Mods without a file for this target, these would have to be dropped to use it
+	pub excluded: Vec<String>,
+}
+
+impl Display for RankedTarget {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let total = self.supported + self.excluded.len();
+
+		if self.excluded.is_empty() {
+			write!(f, "{} {}: all {} mods", self.loader, self.version, total)
+		} else {
+			write!(
+				f,
+				"{} {}: {} of {} mods (score {}), without {}",
+				self.loader,
+				self.version,
+				self.supported,
+				total,
+				self.score,
+				self.excluded.join(", ")
+			)
+		}
+	}
+}
+
+/// Every loader and version at least one mod supports, best first. A target is worth the summed [`Mod::weight`] of the
+/// mods that support it, targets missing a [`Mod::required`] mod are left out, and ties go to the newer version.
+/// Only mods in `mods` count, so stale files in mods/ don't affect the ranking. Versions are classified and ordered by
+/// `catalogue`, and only loaders in `loaders` are ranked
+pub fn rank_targets(
+	versions: &BTreeMap<String, ModVersions>,
+	mods: &[Mod],
+	catalogue: &VersionCatalogue,
+	loaders: &[ModLoader],
+	force_version: Option<&str>,
+	allow_snapshots: bool,
+) -> Vec<RankedTarget> {
+	let versions = mods
+		.iter()
+		.filter_map(|m| versions.get(&m.name).map(|v| (m, v)))
+		.collect::<Vec<_>>();
+
+	let mut ranked = Vec::new();
+	for loader in loaders {
+		let targets = versions
+			.iter()
+			.flat_map(|(_, mod_versions)| mod_versions.get(loader).iter())
+			.filter(|version| {
+				force_version.map_or(
+					allow_snapshots || catalogue.is_release(version.as_str()),
+					|force| **version == MinecraftVersion::new(force),
+				)
+			})
+			.collect::<BTreeSet<_>>();
+
+		for version in targets {
+			let (supporting, excluded): (Vec<_>, Vec<_>) = versions
+				.iter()
+				.partition(|(_, mod_versions)| mod_versions.get(loader).contains(version));
+
+			if excluded.iter().any(|(m, _)| m.required) {
+				continue;
+			}
+
+			ranked.push((
+				version.clone(),
+				RankedTarget {
+					loader: loader.clone(),
+					version: version.to_string(),
+					score: supporting.iter().map(|(m, _)| m.weight()).sum(),
+					supported: supporting.len(),
+					excluded: excluded.iter().map(|(m, _)| m.name.clone()).collect(),
+				},
+			));
+		}
+	}
+
+	// Stable, so equal targets keep the order of `loaders`
+	ranked.sort_by(|(a_version, a), (b_version, b)| {
+		b.score
+			.cmp(&a.score)
+			.then_with(|| catalogue.compare(b_version, a_version))
+	});
+	ranked.truncate(RANKED_TARGETS);
+
+	ranked.into_iter().map(|(_, target)| target).collect()
+}
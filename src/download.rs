@@ -0,0 +1,287 @@
+use {
+	crate::{
+		http::{HttpClient, cache_dir},
+		provider::ResolvedFile,
+		util::load_lockfile,
+	},
+	anyhow::anyhow,
+	sha1::{Digest, Sha1},
+	std::{
+		collections::BTreeSet,
+		fs,
+		path::{Path, PathBuf},
+		sync::Mutex,
+	},
+};
+
+/// How many files are downloaded at once unless config.toml says otherwise
+pub const DEFAULT_PARALLEL_DOWNLOADS: usize = 4;
+
+/// Downloaded files, kept in the cache dir by the sha1 of their contents so every project shares one copy. Projects
+/// that download into the store are remembered, and [`Store::gc`] removes whatever none of their lockfiles use anymore
+pub struct Store {
+	dir: PathBuf,
+}
+
+/// What [`Store::gc`] removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GcReport {
+	pub removed: usize,
+	// In bytes
+	pub freed: u64,
+}
+
+impl Store {
+	/// The store in modpackr's cache dir
+	pub fn open() -> anyhow::Result<Self> {
+		let dir = cache_dir().ok_or(anyhow!("Can't find a cache directory to download into"))?;
+
+		Ok(Self::at(dir.join("store")))
+	}
+
+	pub fn at<T: Into<PathBuf>>(dir: T) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	/// Where contents with this sha1 are kept, they're split into folders by the first two characters
+	pub fn blob_path(&self, sha1: &str) -> anyhow::Result<PathBuf> {
+		check_hash(sha1)?;
+		let sha1 = sha1.to_lowercase();
+
+		Ok(self
+			.dir
+			.join("blobs")
+			.join(&sha1[..2.min(sha1.len())])
+			.join(sha1))
+	}
+
+	/// Where `file` is kept while it's downloading, so an interrupted download can carry on where it stopped
+	pub fn partial_path(&self, file: &ResolvedFile) -> anyhow::Result<PathBuf> {
+		let key = ["sha1", "sha512", "fingerprint"]
+			.iter()
+			.find_map(|algorithm| file.hashes.get(*algorithm))
+			.cloned()
+			.unwrap_or(hex::encode(Sha1::digest(file.file_name.as_bytes())));
+		check_hash(&key)?;
+
+		Ok(self.dir.join("partial").join(format!("{key}.part")))
+	}
+
+	/// The stored copy of `file`, if it's been downloaded before
+	pub fn get(&self, file: &ResolvedFile) -> Option<PathBuf> {
+		file.hashes
+			.get("sha1")
+			.and_then(|sha1| self.blob_path(sha1).ok())
+			.filter(|path| path.is_file())
+	}
+
+	/// Puts `contents` in the store, returning where they ended up
+	pub fn insert(&self, contents: &[u8]) -> anyhow::Result<PathBuf> {
+		let path = self.blob_path(&hex::encode(Sha1::digest(contents)))?;
+
+		if !path.is_file() {
+			fs::create_dir_all(path.parent().unwrap())?;
+			// Written next to the blob and renamed, so nothing ever sees half a file under its hash
+			let temporary = path.with_extension("tmp");
+			fs::write(&temporary, contents)?;
+			fs::rename(&temporary, &path)?;
+		}
+
+		Ok(path)
+	}
+
+	/// Remembers that `project_dir` uses the store, so gc keeps the files in its lockfile
+	pub fn add_root(&self, project_dir: &Path) -> anyhow::Result<()> {
+		let project_dir = project_dir.canonicalize()?;
+		let mut roots = self.roots();
+
+		if !roots.contains(&project_dir) {
+			roots.push(project_dir);
+			self.write_roots(&roots)?;
+		}
+
+		Ok(())
+	}
+
+	/// Every project that has downloaded into the store
+	pub fn roots(&self) -> Vec<PathBuf> {
+		fs::read_to_string(self.dir.join("roots.json"))
+			.ok()
+			.and_then(|contents| serde_json::from_str(&contents).ok())
+			.unwrap_or_default()
+	}
+
+	fn write_roots(&self, roots: &[PathBuf]) -> anyhow::Result<()> {
+		fs::create_dir_all(&self.dir)?;
+		fs::write(
+			self.dir.join("roots.json"),
+			serde_json::to_string_pretty(roots)?,
+		)?;
+
+		Ok(())
+	}
+
+	/// Removes every stored file that isn't in the lockfile of a project that uses the store, along with unfinished
+	/// downloads. Projects that were deleted stop counting
+	pub fn gc(&self) -> anyhow::Result<GcReport> {
+		let roots = self
+			.roots()
+			.into_iter()
+			.filter(|root| root.join("modpack.ron").is_file())
+			.collect::<Vec<_>>();
+
+		let mut referenced = BTreeSet::new();
+		for root in roots.iter() {
+			let lockfile = load_lockfile(root)?;
+			referenced.extend(
+				lockfile
+					.iter()
+					.flat_map(|lockfile| lockfile.mods.iter())
+					.flat_map(|m| m.files.iter())
+					.filter_map(|file| file.hashes.get("sha1"))
+					.map(|sha1| sha1.to_lowercase()),
+			);
+		}
+
+		let mut report = GcReport::default();
+		for folder in read_dir_paths(&self.dir.join("blobs"))? {
+			for blob in read_dir_paths(&folder)? {
+				let name = blob.file_name().and_then(|name| name.to_str());
+				if name.is_some_and(|name| referenced.contains(name)) {
+					continue;
+				}
+
+				report.freed += fs::metadata(&blob)?.len();
+				report.removed += 1;
+				fs::remove_file(&blob)?;
+			}
+		}
+
+		for partial in read_dir_paths(&self.dir.join("partial"))? {
+			report.freed += fs::metadata(&partial)?.len();
+			report.removed += 1;
+			fs::remove_file(&partial)?;
+		}
+
+		self.write_roots(&roots)?;
+
+		Ok(report)
+	}
+}
+
+/// The paths in `dir`, or none if it doesn't exist
+fn read_dir_paths(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+	if !dir.is_dir() {
+		return Ok(Vec::new());
+	}
+
+	fs::read_dir(dir)?.map(|entry| Ok(entry?.path())).collect()
+}
+
+/// Fetches files into a [`Store`], a few at a time
+pub struct Downloader {
+	store: Store,
+	http: HttpClient,
+	parallel: usize,
+}
+
+impl Downloader {
+	pub fn new(store: Store, parallel: usize) -> Self {
+		Self {
+			store,
+			http: HttpClient::new(),
+			parallel: parallel.max(1),
+		}
+	}
+
+	/// Makes sure `file` is in the store, downloading and verifying it if it isn't, and returns where it is
+	pub fn fetch(&self, file: &ResolvedFile) -> anyhow::Result<PathBuf> {
+		if let Some(path) = self.store.get(file) {
+			return Ok(path);
+		}
+
+		let url = file.url.as_ref().ok_or(anyhow!(
+			"{} has no download URL and must be downloaded manually",
+			file.file_name
+		))?;
+
+		let partial = self.store.partial_path(file)?;
+		fs::create_dir_all(partial.parent().unwrap())?;
+		self.http.download_to(url, &partial)?;
+
+		let contents = fs::read(&partial)?;
+		// A partial file that doesn't check out can't be resumed either, so the next attempt starts over
+		let verified = file.verify(&contents);
+		fs::remove_file(&partial)?;
+		verified?;
+
+		self.store.insert(&contents)
+	}
+
+	/// [`Self::fetch`] for every file, with up to the parallel limit downloading at once. Returns the paths in the
+	/// same order as `files`
+	pub fn fetch_all(&self, files: &[&ResolvedFile]) -> anyhow::Result<Vec<PathBuf>> {
+		// The same file twice would have two threads writing one partial download
+		let mut unique = Vec::new();
+		let positions = files
+			.iter()
+			.map(|file| {
+				let key = self.store.partial_path(file)?;
+				Ok(match unique.iter().position(|(other, _)| *other == key) {
+					Some(position) => position,
+					None => {
+						unique.push((key, *file));
+						unique.len() - 1
+					},
+				})
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+
+		let next = Mutex::new(0);
+		let results = unique.iter().map(|_| Mutex::new(None)).collect::<Vec<_>>();
+
+		std::thread::scope(|scope| {
+			for _ in 0..self.parallel.min(unique.len()) {
+				scope.spawn(|| {
+					loop {
+						let index = {
+							let mut next = next.lock().unwrap();
+							*next += 1;
+							*next - 1
+						};
+						let Some((_, file)) = unique.get(index) else {
+							break;
+						};
+
+						*results[index].lock().unwrap() = Some(self.fetch(file));
+					}
+				});
+			}
+		});
+
+		let results = results
+			.into_iter()
+			.map(|result| {
+				result
+					.into_inner()
+					.unwrap()
+					.unwrap_or(Err(anyhow!("Download never ran")))
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+
+		Ok(positions
+			.into_iter()
+			.map(|position| results[position].clone())
+			.collect())
+	}
+}
+
+/// Hashes become file names in the store, so anything but hex digits (which covers curseforge's decimal fingerprints)
+/// is refused rather than turned into a path
+fn check_hash(hash: &str) -> anyhow::Result<()> {
+	if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+		return Err(anyhow!("{} is not a valid hash", hash));
+	}
+
+	Ok(())
+}
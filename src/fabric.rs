@@ -1,15 +1,41 @@
-use {reqwest::blocking::get, serde::Deserialize};
+use {crate::http::HttpClient, anyhow::anyhow, serde::Deserialize};
 
-const API_BASE: &str = "https://meta.fabricmc.net/v2";
+pub const API_BASE: &str = "https://meta.fabricmc.net/v2";
 
-pub fn get_stable_fabric_for_version<T: Into<String>>(version: T) -> anyhow::Result<String> {
-	let url = format!("{API_BASE}/versions/loader/{}", version.into());
-	let response = get(&url)?.error_for_status()?;
-
-	let versions_response: Vec<LoaderVersion> = response.json()?;
+pub fn get_stable_fabric_for_version<T: Into<String>>(
+	api_base: &str,
+	version: T,
+) -> anyhow::Result<String> {
+	let url = format!("{}/versions/loader/{}", api_base, version.into());
+	let versions_response: Vec<LoaderVersion> = HttpClient::new().get_json(&url, &[])?;
 	Ok(format!("fabric-{}", versions_response[0].loader.version))
 }
 
+/// Fabric's server launcher for a loader version, which fetches the vanilla server and libraries on its first start
+pub fn get_server_launcher_url(
+	api_base: &str,
+	mc_version: &str,
+	loader_version: &str,
+) -> anyhow::Result<String> {
+	let url = format!("{}/versions/installer", api_base);
+	let installers: Vec<InstallerVersion> = HttpClient::new().get_json(&url, &[])?;
+	let installer = installers
+		.iter()
+		.find(|installer| installer.stable)
+		.ok_or(anyhow!("No stable fabric installer found in {}", url))?;
+
+	Ok(format!(
+		"{}/versions/loader/{}/{}/{}/server/jar",
+		api_base, mc_version, loader_version, installer.version
+	))
+}
+
+#[derive(Deserialize)]
+struct InstallerVersion {
+	version: String,
+	stable: bool,
+}
+
 #[derive(Deserialize)]
 struct LoaderVersion {
 	loader: Loader,
@@ -1,13 +1,18 @@
 use {
+	crate::http::HttpClient,
 	anyhow::anyhow,
 	quick_xml::{Reader, events::Event},
-	reqwest::blocking::get,
 };
 
-pub fn get_latest_forge_version<T: Into<String>>(mc_version: T) -> anyhow::Result<String> {
+pub const MAVEN_BASE: &str = "https://maven.minecraftforge.net";
+
+pub fn get_latest_forge_version<T: Into<String>>(
+	maven_base: &str,
+	mc_version: T,
+) -> anyhow::Result<String> {
 	let mc_version = mc_version.into();
-	let url = "https://maven.minecraftforge.net/net/minecraftforge/forge/maven-metadata.xml";
-	let response = get(url)?.text()?;
+	let url = format!("{maven_base}/net/minecraftforge/forge/maven-metadata.xml");
+	let response = HttpClient::new().get_text(&url, &[])?;
 
 	let mut reader = Reader::from_str(&response);
 	reader.trim_text(true);
@@ -43,3 +48,8 @@ pub fn get_latest_forge_version<T: Into<String>>(mc_version: T) -> anyhow::Resul
 		.map(|s| format!("forge{}", &s[mc_version.len()..]))
 		.ok_or(anyhow!("No stable versions found in {}", url))
 }
+
+/// Where forge `forge_version` (like `47.2.0`) is, both in the maven and in a server's libraries folder
+pub fn artifact_path(mc_version: &str, forge_version: &str) -> String {
+	format!("net/minecraftforge/forge/{mc_version}-{forge_version}")
+}
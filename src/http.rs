@@ -0,0 +1,265 @@
+use {
+	anyhow::anyhow,
+	reqwest::{
+		StatusCode,
+		blocking::Client,
+		header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE},
+	},
+	serde::{Deserialize, Serialize, de::DeserializeOwned},
+	sha1::{Digest, Sha1},
+	std::{
+		fs::{self, OpenOptions},
+		path::{Path, PathBuf},
+		sync::atomic::{AtomicBool, Ordering},
+		time::{Duration, SystemTime, UNIX_EPOCH},
+	},
+};
+
+/// How long a cached response is used without asking the server if it changed
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Mod jars and installers can be big, so downloads get longer than the client's usual 30 seconds
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// In offline mode every request is answered from the cache, and fails if it isn't there
+pub fn set_offline(offline: bool) {
+	OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+	OFFLINE.load(Ordering::Relaxed) || std::env::var("MODPACKR_OFFLINE").is_ok_and(|v| v != "0")
+}
+
+/// Where modpackr keeps things it downloaded, `MODPACKR_CACHE_DIR` overrides the user's cache dir
+pub fn cache_dir() -> Option<PathBuf> {
+	std::env::var("MODPACKR_CACHE_DIR")
+		.ok()
+		.map(PathBuf::from)
+		.or(dirs::cache_dir().map(|dir| dir.join("modpackr")))
+}
+
+#[derive(Deserialize, Serialize)]
+struct CacheEntry {
+	url: String,
+	// Seconds since the unix epoch when the server last confirmed this body
+	fetched_at: u64,
+	etag: Option<String>,
+	last_modified: Option<String>,
+	body: String,
+}
+
+/// A blocking HTTP client that keeps GET responses on disk, keyed by URL. Fresh entries are used as is, stale ones
+/// are revalidated with their ETag or Last-Modified date
+pub struct HttpClient {
+	client: Client,
+	ttl: Duration,
+	// Some APIs (curseforge) get upset if they're called too often, this is waited before every request that
+	// actually goes out
+	rate_limit: Option<Duration>,
+}
+
+impl Default for HttpClient {
+	fn default() -> Self {
+		let ttl = std::env::var("MODPACKR_CACHE_TTL")
+			.ok()
+			.and_then(|secs| secs.parse().ok())
+			.map_or(DEFAULT_TTL, Duration::from_secs);
+
+		Self {
+			client: Client::new(),
+			ttl,
+			rate_limit: None,
+		}
+	}
+}
+
+impl HttpClient {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	pub fn with_rate_limit(mut self, delay: Duration) -> Self {
+		self.rate_limit = Some(delay);
+		self
+	}
+
+	pub fn get_json<T: DeserializeOwned>(
+		&self,
+		url: &str,
+		headers: &[(&str, &str)],
+	) -> anyhow::Result<T> {
+		let body = self.get_text(url, headers)?;
+
+		serde_json::from_str(&body).map_err(|e| anyhow!("Invalid response from {}: {e}", url))
+	}
+
+	pub fn get_text(&self, url: &str, headers: &[(&str, &str)]) -> anyhow::Result<String> {
+		let path = cache_path(url);
+		let cached = path
+			.as_ref()
+			.and_then(|path| fs::read_to_string(path).ok())
+			.and_then(|contents| serde_json::from_str::<CacheEntry>(&contents).ok())
+			.filter(|entry| entry.url == url);
+
+		if is_offline() {
+			return cached.map(|entry| entry.body).ok_or(anyhow!(
+				"Running offline and {} is not in the cache, run once without --offline to fill it",
+				url
+			));
+		}
+
+		if let Some(ref entry) = cached &&
+			now().saturating_sub(entry.fetched_at) < self.ttl.as_secs()
+		{
+			return Ok(entry.body.clone());
+		}
+
+		if let Some(delay) = self.rate_limit {
+			std::thread::sleep(delay);
+		}
+
+		let mut request = self.client.get(url);
+		for (name, value) in headers {
+			request = request.header(*name, *value);
+		}
+		if let Some(ref entry) = cached {
+			if let Some(ref etag) = entry.etag {
+				request = request.header(IF_NONE_MATCH, etag);
+			}
+			if let Some(ref last_modified) = entry.last_modified {
+				request = request.header(IF_MODIFIED_SINCE, last_modified);
+			}
+		}
+
+		let response = request.send()?;
+
+		let entry = if response.status() == StatusCode::NOT_MODIFIED &&
+			let Some(entry) = cached
+		{
+			CacheEntry {
+				fetched_at: now(),
+				..entry
+			}
+		} else {
+			let response = response.error_for_status()?;
+			let header = |name| {
+				response
+					.headers()
+					.get(name)
+					.and_then(|value| value.to_str().ok())
+					.map(str::to_string)
+			};
+
+			CacheEntry {
+				url: url.to_owned(),
+				fetched_at: now(),
+				etag: header(ETAG),
+				last_modified: header(LAST_MODIFIED),
+				body: response.text()?,
+			}
+		};
+
+		// The cache is only an optimization, so failing to write it isn't worth failing the request over
+		if let Some(path) = path {
+			let _ = fs::create_dir_all(path.parent().unwrap())
+				.and_then(|_| fs::write(&path, serde_json::to_string(&entry).unwrap_or_default()));
+		}
+
+		Ok(entry.body)
+	}
+
+	/// Downloads a file without caching it, so this fails in offline mode
+	pub fn download(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+		if is_offline() {
+			return Err(anyhow!("Can't download {} while running offline", url));
+		}
+
+		if let Some(delay) = self.rate_limit {
+			std::thread::sleep(delay);
+		}
+
+		Ok(self
+			.client
+			.get(url)
+			.timeout(DOWNLOAD_TIMEOUT)
+			.send()?
+			.error_for_status()?
+			.bytes()?
+			.to_vec())
+	}
+
+	/// Downloads `url` into `path`. If `path` already has the start of the file from an interrupted download, only the
+	/// rest of it is requested
+	pub fn download_to(&self, url: &str, path: &Path) -> anyhow::Result<()> {
+		if is_offline() {
+			return Err(anyhow!("Can't download {} while running offline", url));
+		}
+
+		if let Some(delay) = self.rate_limit {
+			std::thread::sleep(delay);
+		}
+
+		let existing = fs::metadata(path).map_or(0, |metadata| metadata.len());
+		let mut request = self.client.get(url).timeout(DOWNLOAD_TIMEOUT);
+		if existing > 0 {
+			request = request.header(RANGE, format!("bytes={existing}-"));
+		}
+
+		let mut response = request.send()?;
+		let mut file = match response.status() {
+			StatusCode::PARTIAL_CONTENT => OpenOptions::new().append(true).open(path)?,
+			// The partial file already has everything, checking its hash will tell if it's actually the right file
+			StatusCode::RANGE_NOT_SATISFIABLE => return Ok(()),
+			// Servers that don't support ranges send the whole file again
+			_ => {
+				response = response.error_for_status()?;
+				fs::File::create(path)?
+			},
+		};
+		response.copy_to(&mut file)?;
+
+		Ok(())
+	}
+
+	/// POST requests aren't cached, so these fail in offline mode
+	pub fn post_json<T: DeserializeOwned, B: Serialize>(
+		&self,
+		url: &str,
+		body: &B,
+	) -> anyhow::Result<T> {
+		if is_offline() {
+			return Err(anyhow!("Can't reach {} while running offline", url));
+		}
+
+		if let Some(delay) = self.rate_limit {
+			std::thread::sleep(delay);
+		}
+
+		Ok(self
+			.client
+			.post(url)
+			.json(body)
+			.send()?
+			.error_for_status()?
+			.json()?)
+	}
+}
+
+fn cache_path(url: &str) -> Option<PathBuf> {
+	let hash = hex::encode(Sha1::digest(url.as_bytes()));
+
+	cache_dir().map(|dir| dir.join("http").join(format!("{hash}.json")))
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |d| d.as_secs())
+}
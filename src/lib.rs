@@ -1,39 +1,253 @@
 #![allow(dead_code)]
 
 use {
+	anyhow::anyhow,
 	curseforge::CurseforgeMod,
+	download::DEFAULT_PARALLEL_DOWNLOADS,
 	modrinth::ModrinthMod,
+	provider::{Channel, FileFilter, Pin, ProviderRef, ResolvedFile, glob_matches},
 	serde::{Deserialize, Serialize},
 	std::{
 		collections::BTreeSet,
 		fmt::Display,
 		ops::{Add, AddAssign, Sub, SubAssign},
+		str::FromStr,
 	},
+	version::MinecraftVersion,
 };
 
 pub mod curseforge;
+pub mod diagnostics;
+pub mod download;
 pub mod fabric;
 pub mod forge;
+pub mod http;
 pub mod modrinth;
+pub mod mojang;
 pub mod neoforge;
+pub mod provider;
 pub mod quilt;
+pub mod server;
 pub mod util;
+pub mod version;
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
 pub struct Mod {
 	pub name: String,
 
-	// The ids of the mods
-	pub curseforge: Option<CurseforgeMod>,
-	pub modrinth: Option<ModrinthMod>,
+	// Where the mod can be found, a mod with no providers has to be installed manually
+	#[serde(default)]
+	pub providers: Vec<ProviderRef>,
+
+	// Set when `check` added this mod because another one requires it, holds the name of that mod
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub required_by: Option<String>,
+
+	// How much this mod counts when the coverage solver ranks targets, 1 if unset
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub priority: Option<u32>,
+	// The coverage solver never suggests a target this mod doesn't support
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub required: bool,
+
+	// Keeps the mod at this file or version instead of the newest one
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pin: Option<Pin>,
+
+	// Overrides the channel from config.toml for this mod
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub channel: Option<Channel>,
+
+	// Override what the providers say about where this mod runs
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub client: Option<Support>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub server: Option<Support>,
+
+	// The jar of a manual mod, kept in the project so exports can ship it
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub jar: Option<ManualJar>,
+
+	// Written by older versions of modpackr, moved into `providers` by `util::load_modpack`
+	#[serde(default, skip_serializing)]
+	curseforge: Option<CurseforgeMod>,
+	#[serde(default, skip_serializing)]
+	modrinth: Option<ModrinthMod>,
+}
+
+impl Mod {
+	pub fn new<T: Into<String>>(name: T, providers: Vec<ProviderRef>) -> Self {
+		Self {
+			name: name.into(),
+			providers,
+			..Default::default()
+		}
+	}
+
+	/// The reference to this mod on the named provider
+	pub fn provider(&self, name: &str) -> Option<&ProviderRef> {
+		self.providers.iter().find(|p| p.provider == name)
+	}
+
+	pub fn is_manual(&self) -> bool {
+		self.providers.is_empty()
+	}
+
+	/// Whether exports ship this mod's jar themselves instead of leaving it for the player to install
+	pub fn is_bundled(&self) -> bool {
+		self.jar.as_ref().is_some_and(|jar| jar.redistributable)
+	}
+
+	/// Whether this mod was added automatically as a dependency of another one
+	pub fn is_auto_added(&self) -> bool {
+		self.required_by.is_some()
+	}
+
+	/// The files this mod may use when the pack allows `channel`
+	pub fn file_filter(&self, channel: Channel) -> FileFilter {
+		FileFilter {
+			channel: self.channel.unwrap_or(channel),
+			pin: self.pin.clone(),
+		}
+	}
+
+	/// Where this mod runs: the overrides from modpack.ron, then what the locked files say, then required on both
+	pub fn sides(&self, locked: Option<&LockedMod>) -> Sides {
+		let detected = locked.and_then(LockedMod::sides).unwrap_or_default();
+
+		Sides {
+			client: self.client.unwrap_or(detected.client),
+			server: self.server.unwrap_or(detected.server),
+		}
+	}
+
+	/// What this mod is worth to the coverage solver
+	pub fn weight(&self) -> u32 {
+		self.priority.unwrap_or(1)
+	}
+
+	/// Whether this mod is `project`, by id or slug on the same provider. Slugs aren't shared between providers, so the
+	/// same slug somewhere else can be a different mod
+	pub fn is_project(&self, project: &ProviderRef) -> bool {
+		self.providers.iter().any(|p| {
+			p.provider == project.provider && (p.id == project.id || p.slug == project.slug)
+		})
+	}
+
+	/// Moves the ids stored by older versions of modpackr into `providers`
+	pub(crate) fn migrate(&mut self) {
+		if let Some(modrinth) = self.modrinth.take() {
+			self.providers.push(modrinth.into());
+		}
+		if let Some(curseforge) = self.curseforge.take() {
+			self.providers.push(curseforge.into());
+		}
+	}
+}
+
+/// A manual mod's jar, stored in the project's `jars/` folder. Exports put it in `overrides/mods/`, but only once the
+/// mod's license has been confirmed to allow shipping it in a modpack
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub struct ManualJar {
+	// The file name in `jars/` and in the exported `mods/` folder
+	pub file_name: String,
+	pub sha1: String,
+	// Where the jar was downloaded from, if it didn't come from a local file
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	// Whether the user confirmed the mod's license allows redistributing it
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub redistributable: bool,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
 pub struct ModVersions {
-	pub fabric: BTreeSet<String>,
-	pub forge: BTreeSet<String>,
-	pub neo_forge: BTreeSet<String>,
-	pub quilt: BTreeSet<String>,
+	pub fabric: BTreeSet<MinecraftVersion>,
+	pub forge: BTreeSet<MinecraftVersion>,
+	pub neo_forge: BTreeSet<MinecraftVersion>,
+	pub quilt: BTreeSet<MinecraftVersion>,
+}
+
+impl ModVersions {
+	/// The minecraft versions supported on `loader`
+	pub fn get(&self, loader: &ModLoader) -> &BTreeSet<MinecraftVersion> {
+		match loader {
+			ModLoader::Fabric => &self.fabric,
+			ModLoader::Quilt => &self.quilt,
+			ModLoader::Forge => &self.forge,
+			ModLoader::Neoforge => &self.neo_forge,
+		}
+	}
+
+	pub fn get_mut(&mut self, loader: &ModLoader) -> &mut BTreeSet<MinecraftVersion> {
+		match loader {
+			ModLoader::Fabric => &mut self.fabric,
+			ModLoader::Quilt => &mut self.quilt,
+			ModLoader::Forge => &mut self.forge,
+			ModLoader::Neoforge => &mut self.neo_forge,
+		}
+	}
+
+	/// These versions plus the ones `bridges` make work, so with the default bridges a fabric 1.20.1 file also counts
+	/// for quilt 1.20.1
+	pub fn bridged(&self, bridges: &[Bridge]) -> Self {
+		let mut bridged = self.clone();
+
+		for bridge in bridges {
+			let accepted = self
+				.get(&bridge.accepts)
+				.iter()
+				.filter(|version| bridge.applies(version.as_str()))
+				.cloned();
+			bridged.get_mut(&bridge.loader).extend(accepted);
+		}
+
+		bridged
+	}
+}
+
+/// Lets `loader` use mods published for `accepts`, like quilt loading fabric mods
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub struct Bridge {
+	pub loader: ModLoader,
+	pub accepts: ModLoader,
+	// The minecraft versions this works on, every version if it's empty
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub versions: Vec<String>,
+}
+
+impl Bridge {
+	/// Quilt loads fabric mods, and neoforge on 1.20.1 is still close enough to forge to load its mods
+	pub fn defaults() -> Vec<Bridge> {
+		vec![
+			Bridge {
+				loader: ModLoader::Quilt,
+				accepts: ModLoader::Fabric,
+				versions: Vec::new(),
+			},
+			Bridge {
+				loader: ModLoader::Neoforge,
+				accepts: ModLoader::Forge,
+				versions: vec!["1.20.1".to_owned()],
+			},
+		]
+	}
+
+	pub fn applies(&self, mc_version: &str) -> bool {
+		self.versions.is_empty() || self.versions.iter().any(|v| v == mc_version)
+	}
+}
+
+impl Display for Bridge {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} loads {} mods", self.loader, self.accepts)?;
+
+		if !self.versions.is_empty() {
+			write!(f, " on {}", self.versions.join(", "))?;
+		}
+
+		Ok(())
+	}
 }
 
 impl AddAssign for ModVersions {
@@ -125,12 +339,276 @@ pub struct Modpack {
 	pub mods: Vec<Mod>,
 }
 
-// TODO: Make this useful, and maybe add more stuff later
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+/// The exact files a pack resolved to, this is what gets stored in modpack.lock. Exports only ship what's in here, so
+/// the same lockfile always produces the same pack until it's deliberately updated
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Lockfile {
+	pub loader: ModLoader,
+	pub minecraft_version: String,
+	// The curseforge style `<loader>-<version>` that util::get_loader_version returns
+	pub loader_version: String,
+	pub mods: Vec<LockedMod>,
+}
+
+impl Lockfile {
+	pub fn get(&self, name: &str) -> Option<&LockedMod> {
+		self.mods.iter().find(|m| m.name == name)
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct LockedMod {
+	pub name: String,
+	// One file for each of the mod's providers
+	pub files: Vec<ResolvedFile>,
+	// The pin and channel the files were picked with, if either changes they're picked again
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pin: Option<Pin>,
+	#[serde(default)]
+	pub channel: Channel,
+}
+
+impl LockedMod {
+	pub fn file(&self, provider: &str) -> Option<&ResolvedFile> {
+		self.files.iter().find(|file| file.provider == provider)
+	}
+
+	/// Where the providers say the mod runs, from the first file that says
+	pub fn sides(&self) -> Option<Sides> {
+		self.files.iter().find_map(|file| file.sides)
+	}
+}
+
+/// One half of a minecraft install, exports can be made for just one of them
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Side {
+	Client,
+	Server,
+}
+
+/// Whether a mod has to be, may be or can't be installed on a side. These are the values modrinth uses for `env`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Support {
+	#[default]
+	Required,
+	Optional,
+	Unsupported,
+}
+
+/// Where a mod runs, mods nobody said anything about are required on both sides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub struct Sides {
+	pub client: Support,
+	pub server: Support,
+}
+
+impl Sides {
+	pub fn get(&self, side: Side) -> Support {
+		match side {
+			Side::Client => self.client,
+			Side::Server => self.server,
+		}
+	}
+
+	/// Whether the mod belongs in an install of `side` at all
+	pub fn supports(&self, side: Side) -> bool {
+		self.get(side) != Support::Unsupported
+	}
+}
+
+impl Display for Sides {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match (self.client, self.server) {
+			(Support::Unsupported, _) => write!(f, "server only"),
+			(_, Support::Unsupported) => write!(f, "client only"),
+			(Support::Required, Support::Required) => write!(f, "both sides"),
+			(client, server) => write!(f, "client {client}, server {server}"),
+		}
+	}
+}
+
+/// Bump this whenever a change to [`Config`] needs a migration in `util::load_config`
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+#[serde(default)]
 pub struct Config {
-	export: ExportFormat,
-	version: Option<String>,
-	loader: Option<ModLoader>,
+	// Configs written before the schema was versioned don't have this, so they load as 0
+	#[serde(default)]
+	pub schema_version: u32,
+	// Format used by `export` when no format flag is given
+	pub export: ExportFormat,
+	// Forces `check` to only consider this minecraft version
+	pub version: Option<String>,
+	// Forces `check` to only consider this loader
+	pub loader: Option<ModLoader>,
+	// Loaders in the order `check` prefers them, any that are left out come after these in the default order
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub loader_order: Vec<ModLoader>,
+	// Whether `check` goes by loader_order first or by the newest minecraft version
+	pub strategy: Strategy,
+	// Lets `check` pick snapshots, pre-releases and release candidates, not just full releases
+	pub allow_snapshots: bool,
+	// The least stable files mods may use, mods can override this
+	pub channel: Channel,
+	// How `check` picks a target, see [`Solver`]
+	pub solver: Solver,
+	// Loaders that can use mods made for another loader, `bridges = []` turns this off
+	pub bridges: Vec<Bridge>,
+	// How many files are downloaded at once
+	pub parallel_downloads: usize,
+	// Which files in the override folders go into exports
+	pub overrides: OverrideFilter,
+	// Overrides for where the APIs are, for mirrors or testing against a mock server
+	#[serde(skip_serializing_if = "Endpoints::is_empty")]
+	pub endpoints: Endpoints,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			schema_version: CONFIG_SCHEMA_VERSION,
+			export: ExportFormat::default(),
+			version: None,
+			loader: None,
+			loader_order: Vec::new(),
+			strategy: Strategy::default(),
+			allow_snapshots: false,
+			channel: Channel::default(),
+			solver: Solver::default(),
+			bridges: Bridge::defaults(),
+			parallel_downloads: DEFAULT_PARALLEL_DOWNLOADS,
+			overrides: OverrideFilter::default(),
+			endpoints: Endpoints::default(),
+		}
+	}
+}
+
+impl Config {
+	/// The loaders `check` may pick, most preferred first
+	pub fn loaders(&self) -> Vec<ModLoader> {
+		if let Some(ref loader) = self.loader {
+			return vec![loader.clone()];
+		}
+
+		let mut loaders = Vec::new();
+		for loader in self.loader_order.iter().chain(ModLoader::ALL.iter()) {
+			if !loaders.contains(loader) {
+				loaders.push(loader.clone());
+			}
+		}
+
+		loaders
+	}
+}
+
+/// Which files in `overrides/`, `client-overrides/` and `server-overrides/` are packaged into exports, set in the
+/// `[overrides]` table of config.toml. Globs without a `/` match any file or folder name, ones with a `/` match the
+/// whole path inside the override folder
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+#[serde(default)]
+pub struct OverrideFilter {
+	// Only files matching one of these are exported, or every file if it's empty
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub include: Vec<String>,
+	// Files matching any of these are left out even if they're included
+	pub exclude: Vec<String>,
+}
+
+impl Default for OverrideFilter {
+	fn default() -> Self {
+		Self {
+			include: Vec::new(),
+			// Per-player settings and logs from testing the pack shouldn't end up in it
+			exclude: vec!["*.log".to_owned(), "options.txt".to_owned()],
+		}
+	}
+}
+
+impl OverrideFilter {
+	/// Whether the file at `path`, relative to its override folder and separated by `/`, is exported
+	pub fn allows(&self, path: &str) -> bool {
+		let matches = |pattern: &String| {
+			if pattern.contains('/') {
+				glob_matches(pattern.trim_start_matches('/'), path)
+			} else {
+				path.split('/').any(|part| glob_matches(pattern, part))
+			}
+		};
+
+		(self.include.is_empty() || self.include.iter().any(matches)) &&
+			!self.exclude.iter().any(matches)
+	}
+}
+
+/// Base URLs for every API modpackr talks to. Each one can be set in the `[endpoints]` table of config.toml or with
+/// an environment variable (which wins over config.toml), anything left unset uses the real API
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+#[serde(default)]
+pub struct Endpoints {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub modrinth: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub curseforge: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fabric: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub quilt: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub forge_maven: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub neoforge_maven: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mojang: Option<String>,
+}
+
+impl Endpoints {
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+
+	fn resolve(configured: &Option<String>, env_var: &str, default: &str) -> String {
+		std::env::var(env_var)
+			.ok()
+			.or(configured.clone())
+			.unwrap_or(default.to_owned())
+	}
+
+	pub fn modrinth(&self) -> String {
+		Self::resolve(&self.modrinth, "MODPACKR_MODRINTH_API", modrinth::API_BASE)
+	}
+
+	pub fn curseforge(&self) -> String {
+		Self::resolve(
+			&self.curseforge,
+			"MODPACKR_CURSEFORGE_API",
+			curseforge::API_BASE,
+		)
+	}
+
+	pub fn fabric(&self) -> String {
+		Self::resolve(&self.fabric, "MODPACKR_FABRIC_API", fabric::API_BASE)
+	}
+
+	pub fn quilt(&self) -> String {
+		Self::resolve(&self.quilt, "MODPACKR_QUILT_API", quilt::API_BASE)
+	}
+
+	pub fn forge_maven(&self) -> String {
+		Self::resolve(&self.forge_maven, "MODPACKR_FORGE_MAVEN", forge::MAVEN_BASE)
+	}
+
+	pub fn neoforge_maven(&self) -> String {
+		Self::resolve(
+			&self.neoforge_maven,
+			"MODPACKR_NEOFORGE_MAVEN",
+			neoforge::MAVEN_BASE,
+		)
+	}
+
+	pub fn mojang(&self) -> String {
+		Self::resolve(&self.mojang, "MODPACKR_MOJANG_API", mojang::API_BASE)
+	}
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
@@ -139,6 +617,26 @@ pub enum ExportFormat {
 	Modrinth,
 	Curseforge,
 	Modpackr,
+	// A folder (and zip of it) with everything a dedicated server needs
+	Server,
+}
+
+/// `Strict` only accepts a target every mod supports. `Coverage` also ranks every target by how much of the pack it
+/// supports, so there's something to go on when no target supports everything
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Solver {
+	#[default]
+	Strict,
+	Coverage,
+}
+
+/// `LoaderFirst` picks the newest version on the most preferred loader that works at all. `NewestFirst` picks the newest
+/// version that works on any loader, and only uses the loader order to break ties
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Strategy {
+	#[default]
+	LoaderFirst,
+	NewestFirst,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
@@ -150,6 +648,173 @@ pub enum ModLoader {
 	Neoforge,
 }
 
+impl Display for ExportFormat {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Modrinth => "modrinth",
+				Self::Curseforge => "curseforge",
+				Self::Modpackr => "modpackr",
+				Self::Server => "server",
+			}
+		)
+	}
+}
+
+impl FromStr for ExportFormat {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"modrinth" | "mrpack" => Ok(Self::Modrinth),
+			"curseforge" => Ok(Self::Curseforge),
+			"modpackr" => Ok(Self::Modpackr),
+			"server" => Ok(Self::Server),
+			_ => Err(anyhow!(
+				"Unknown export format {}, expected modrinth, curseforge, modpackr or server",
+				s
+			)),
+		}
+	}
+}
+
+impl ModLoader {
+	/// Every loader, in the default order
+	pub const ALL: [ModLoader; 4] = [
+		ModLoader::Fabric,
+		ModLoader::Quilt,
+		ModLoader::Forge,
+		ModLoader::Neoforge,
+	];
+}
+
+impl Display for Strategy {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::LoaderFirst => "loader-first",
+				Self::NewestFirst => "newest-first",
+			}
+		)
+	}
+}
+
+impl FromStr for Strategy {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"loader-first" => Ok(Self::LoaderFirst),
+			"newest-first" => Ok(Self::NewestFirst),
+			_ => Err(anyhow!(
+				"Unknown strategy {}, expected loader-first or newest-first",
+				s
+			)),
+		}
+	}
+}
+
+impl Display for Side {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Client => "client",
+				Self::Server => "server",
+			}
+		)
+	}
+}
+
+impl FromStr for Side {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"client" => Ok(Self::Client),
+			"server" => Ok(Self::Server),
+			_ => Err(anyhow!("Unknown side {}, expected client or server", s)),
+		}
+	}
+}
+
+impl Display for Support {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Required => "required",
+				Self::Optional => "optional",
+				Self::Unsupported => "unsupported",
+			}
+		)
+	}
+}
+
+impl FromStr for Support {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"required" => Ok(Self::Required),
+			"optional" => Ok(Self::Optional),
+			"unsupported" => Ok(Self::Unsupported),
+			_ => Err(anyhow!(
+				"Unknown side support {}, expected required, optional or unsupported",
+				s
+			)),
+		}
+	}
+}
+
+impl Display for Solver {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Strict => "strict",
+				Self::Coverage => "coverage",
+			}
+		)
+	}
+}
+
+impl FromStr for Solver {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"strict" => Ok(Self::Strict),
+			"coverage" => Ok(Self::Coverage),
+			_ => Err(anyhow!("Unknown solver {}, expected strict or coverage", s)),
+		}
+	}
+}
+
+impl FromStr for ModLoader {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"fabric" => Ok(Self::Fabric),
+			"quilt" => Ok(Self::Quilt),
+			"forge" => Ok(Self::Forge),
+			"neoforge" => Ok(Self::Neoforge),
+			_ => Err(anyhow!(
+				"Unknown mod loader {}, expected fabric, quilt, forge or neoforge",
+				s
+			)),
+		}
+	}
+}
+
 impl Display for ModLoader {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(
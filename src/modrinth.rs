@@ -1,11 +1,22 @@
 use {
-	crate::ModVersions,
+	crate::{
+		ModLoader, ModVersions, Sides,
+		http::HttpClient,
+		provider::{
+			Channel, Dependency, DependencyKind, FileFilter, ModProvider, NoFile, ProviderRef,
+			ResolvedFile,
+		},
+		version::MinecraftVersion,
+	},
 	anyhow::anyhow,
-	reqwest::blocking::get,
+	chrono::{DateTime, Utc},
+	reqwest::Url,
 	serde::{Deserialize, Serialize},
-	std::collections::BTreeSet,
+	std::collections::BTreeMap,
 };
-const API_BASE: &str = "https://api.modrinth.com/v2";
+pub const API_BASE: &str = "https://api.modrinth.com/v2";
+
+pub const PROVIDER: &str = "modrinth";
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
 pub struct ModrinthMod {
@@ -15,77 +26,385 @@ pub struct ModrinthMod {
 	pub slug: String,
 }
 
-pub fn get_mod_from_modrinth<T: Into<String>>(mod_id: T) -> anyhow::Result<ModrinthMod> {
-	let url = format!("{API_BASE}/project/{}", mod_id.into());
-	let res = get(&url)?.error_for_status()?;
+impl From<ModrinthMod> for ProviderRef {
+	fn from(value: ModrinthMod) -> Self {
+		Self {
+			provider: PROVIDER.to_owned(),
+			id: value.id,
+			slug: value.slug,
+			title: value.title,
+		}
+	}
+}
 
-	let response: ModrinthMod = res.json()?;
+pub struct ModrinthClient {
+	api_base: String,
+	http: HttpClient,
+}
 
-	Ok(response)
+impl Default for ModrinthClient {
+	fn default() -> Self {
+		Self::with_base_url(API_BASE)
+	}
 }
 
-pub fn get_modrinth_mod_from_url<T: Into<String>>(url: T) -> anyhow::Result<ModrinthMod> {
-	let url: String = url.into();
+impl ModrinthClient {
+	pub fn new() -> Self {
+		Self::default()
+	}
 
-	let slug = if let Some(slug) = url
-		.trim_end_matches('/')
-		.split('/')
-		.last()
-		.map(str::to_string)
-	{
-		slug
-	} else {
-		return Err(anyhow!("Error parsing url"));
-	};
+	/// Creates a client that talks to a mirror or mock of the modrinth API instead of the real one
+	pub fn with_base_url<T: Into<String>>(api_base: T) -> Self {
+		Self {
+			api_base: api_base.into().trim_end_matches('/').to_owned(),
+			http: HttpClient::new(),
+		}
+	}
 
-	get_mod_from_modrinth(slug)
-}
+	pub fn get_mod<T: Into<String>>(&self, mod_id: T) -> anyhow::Result<ModrinthMod> {
+		let url = format!("{}/project/{}", self.api_base, mod_id.into());
+		self.http.get_json(&url, &[])
+	}
 
-pub fn get_versions_from_modrinth(id: String) -> anyhow::Result<ModVersions> {
-	let url = format!("{API_BASE}/project/{}/version", id);
-	let resp = get(&url)?.error_for_status()?;
+	pub fn get_mods<'a, T: IntoIterator<Item = &'a String>>(
+		&self,
+		ids: T,
+	) -> anyhow::Result<Vec<ModrinthMod>> {
+		let ids = serde_json::to_string(&ids.into_iter().collect::<Vec<_>>())?;
+		// The query is part of the URL so each set of ids gets its own cache entry
+		let url = Url::parse_with_params(&format!("{}/projects", self.api_base), &[("ids", ids)])?;
 
-	let json: serde_json::Value = resp.json()?;
-	let default = Vec::new();
-	let versions = json.as_array().unwrap_or(&default);
+		self.http.get_json(url.as_str(), &[])
+	}
 
-	let mut fabric = BTreeSet::new();
-	let mut forge = BTreeSet::new();
-	let mut neo_forge = BTreeSet::new();
-	let mut quilt = BTreeSet::new();
+	pub fn from_url<T: Into<String>>(&self, url: T) -> anyhow::Result<ModrinthMod> {
+		let url: String = url.into();
 
-	for v in versions {
-		let loaders = v["loaders"]
-			.as_array()
-			.unwrap_or(&vec![])
-			.iter()
-			.filter_map(|l| l.as_str())
-			.map(|s| s.to_lowercase())
-			.collect::<Vec<_>>();
+		let slug = if let Some(slug) = url
+			.trim_end_matches('/')
+			.split('/')
+			.next_back()
+			.map(str::to_string)
+		{
+			slug
+		} else {
+			return Err(anyhow!("Error parsing url"));
+		};
 
-		let game_versions = v["game_versions"]
-			.as_array()
-			.unwrap_or(&vec![])
-			.iter()
-			.filter_map(|g| g.as_str())
-			.map(|s| s.to_string())
-			.collect::<Vec<_>>();
+		self.get_mod(slug)
+	}
+
+	/// Every loader and minecraft version with a version `filter` allows
+	pub fn get_allowed_versions(
+		&self,
+		id: &str,
+		filter: &FileFilter,
+	) -> anyhow::Result<ModVersions> {
+		let mut versions = ModVersions::default();
 
-		for loader in loaders {
-			match loader.as_str() {
-				"fabric" => fabric.extend(game_versions.iter().cloned()),
-				"forge" => forge.extend(game_versions.iter().cloned()),
-				"neoforge" => neo_forge.extend(game_versions.iter().cloned()),
-				"quilt" => quilt.extend(game_versions.iter().cloned()),
-				_ => {},
+		for version in self
+			.get_all_versions(id)?
+			.into_iter()
+			.filter(|version| version.is_allowed(filter))
+		{
+			for loader in version.loaders.iter().filter_map(|l| l.parse().ok()) {
+				versions.get_mut(&loader).extend(
+					version
+						.game_versions
+						.iter()
+						.map(|v| MinecraftVersion::new(v.as_str())),
+				);
 			}
 		}
+
+		Ok(versions)
+	}
+
+	pub fn get_sides<T: Into<String>>(&self, mod_id: T) -> anyhow::Result<ModrinthSides> {
+		let url = format!("{}/project/{}", self.api_base, mod_id.into());
+		self.http.get_json(&url, &[])
+	}
+
+	pub fn get_all_versions<T: Into<String>>(&self, id: T) -> anyhow::Result<Vec<ModrinthVersion>> {
+		let url = format!("{}/project/{}/version", self.api_base, id.into());
+
+		self.http.get_json(&url, &[])
+	}
+
+	/// The newest version `filter` allows for the loader and minecraft version
+	pub fn latest_allowed(
+		&self,
+		id: &str,
+		filter: &FileFilter,
+		loader: &ModLoader,
+		mc_version: &str,
+	) -> anyhow::Result<ModrinthVersion> {
+		// Filtering here rather than with the query lets this share a cache entry with get_allowed_versions
+		let mut versions = self
+			.get_all_versions(id)?
+			.into_iter()
+			.filter(|version| {
+				version.is_allowed(filter) &&
+					!version.files.is_empty() &&
+					version.loaders.contains(&loader.to_string()) &&
+					version.game_versions.iter().any(|v| v == mc_version)
+			})
+			.collect::<Vec<_>>();
+
+		versions.sort_by_key(|version| std::cmp::Reverse(version.date_published));
+
+		Ok(versions.into_iter().next().ok_or_else(|| {
+			NoFile(format!(
+				"No version {} found for {} with loader {} on {}",
+				filter, id, loader, mc_version
+			))
+		})?)
+	}
+
+	pub fn get_version<T: Into<String>>(&self, version_id: T) -> anyhow::Result<ModrinthVersion> {
+		let url = format!("{}/version/{}", self.api_base, version_id.into());
+
+		self.http.get_json(&url, &[])
+	}
+
+	/// The projects a version declares dependencies on, looking up the project for dependencies that only name a
+	/// version
+	pub fn dependencies(&self, version: &ModrinthVersion) -> anyhow::Result<Vec<Dependency>> {
+		let mut dependencies = Vec::new();
+
+		for dependency in version.dependencies.iter() {
+			let kind = match dependency.dependency_type.as_str() {
+				"required" => DependencyKind::Required,
+				"incompatible" => DependencyKind::Incompatible,
+				"embedded" => DependencyKind::Embedded,
+				_ => DependencyKind::Optional,
+			};
+
+			let project_id = match (&dependency.project_id, &dependency.version_id) {
+				(Some(project_id), _) => project_id.clone(),
+				(None, Some(version_id)) => self.get_version(version_id)?.project_id,
+				(None, None) => continue,
+			};
+
+			dependencies.push(Dependency { project_id, kind });
+		}
+
+		Ok(dependencies)
+	}
+
+	/// Looks up the versions that the files with these sha1 hashes belong to, files modrinth doesn't know are left
+	/// out
+	pub fn versions_from_hashes(
+		&self,
+		hashes: &[String],
+	) -> anyhow::Result<BTreeMap<String, ModrinthVersion>> {
+		let url = format!("{}/version_files", self.api_base);
+		let body = serde_json::json!({
+			"hashes": hashes,
+			"algorithm": "sha1",
+		});
+
+		self.http.post_json(&url, &body)
+	}
+}
+
+impl ModProvider for ModrinthClient {
+	fn name(&self) -> &'static str {
+		PROVIDER
+	}
+
+	fn resolve(&self, url_or_slug: &str) -> anyhow::Result<ProviderRef> {
+		Ok(self.from_url(url_or_slug)?.into())
+	}
+
+	fn project(&self, id: &str) -> anyhow::Result<ProviderRef> {
+		Ok(self.get_mod(id)?.into())
+	}
+
+	fn versions(&self, project: &ProviderRef, filter: &FileFilter) -> anyhow::Result<ModVersions> {
+		self.get_allowed_versions(&project.id, filter)
+	}
+
+	fn select_file(
+		&self,
+		project: &ProviderRef,
+		loader: &ModLoader,
+		mc_version: &str,
+		filter: &FileFilter,
+	) -> anyhow::Result<ResolvedFile> {
+		let version = self.latest_allowed(&project.id, filter, loader, mc_version)?;
+		let file = version.primary_file().ok_or_else(|| {
+			NoFile(format!(
+				"{} has no files for version {}",
+				project.title, version.id
+			))
+		})?;
+
+		Ok(ResolvedFile {
+			provider: PROVIDER.to_owned(),
+			project_id: project.id.clone(),
+			file_id: version.id.clone(),
+			file_name: file.filename.clone(),
+			hashes: BTreeMap::from([
+				("sha1".to_owned(), file.hashes.sha1.clone()),
+				("sha512".to_owned(), file.hashes.sha512.clone()),
+			]),
+			url: Some(file.url.clone()),
+			size: file.size,
+			dependencies: self.dependencies(&version)?,
+			bridged_from: None,
+			sides: None,
+		})
+	}
+
+	fn sides(&self, project: &ProviderRef, _: &ResolvedFile) -> anyhow::Result<Option<Sides>> {
+		Ok(Some(self.get_sides(&project.id)?.sides()))
+	}
+
+	fn project_url(&self, project: &ProviderRef) -> String {
+		format!("https://modrinth.com/mod/{}", project.slug)
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthSides {
+	pub client_side: String, // required, optional, unsupported or unknown
+	pub server_side: String,
+}
+
+impl ModrinthSides {
+	/// Unknown sides count as required
+	pub fn sides(&self) -> Sides {
+		let support = |s: &str| s.parse().unwrap_or_default();
+
+		Sides {
+			client: support(&self.client_side),
+			server: support(&self.server_side),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthVersion {
+	pub id: String,
+	pub project_id: String,
+	pub name: String,
+	pub version_number: String,
+	pub version_type: String, // release, beta or alpha
+	pub date_published: DateTime<Utc>,
+	pub loaders: Vec<String>,
+	pub game_versions: Vec<String>,
+	pub files: Vec<ModrinthVersionFile>,
+	#[serde(default)]
+	pub dependencies: Vec<ModrinthDependency>,
+}
+
+impl ModrinthVersion {
+	pub fn channel(&self) -> Channel {
+		match self.version_type.as_str() {
+			"release" => Channel::Release,
+			"beta" => Channel::Beta,
+			_ => Channel::Alpha,
+		}
+	}
+
+	pub fn is_allowed(&self, filter: &FileFilter) -> bool {
+		filter.allows(
+			PROVIDER,
+			&self.id,
+			&[&self.version_number],
+			&self.game_versions,
+			self.channel(),
+		)
 	}
 
-	Ok(ModVersions {
-		fabric,
-		forge,
-		neo_forge,
-		quilt,
-	})
+	/// The file marked as primary, or the first one if the author didn't mark any
+	pub fn primary_file(&self) -> Option<&ModrinthVersionFile> {
+		self.files
+			.iter()
+			.find(|file| file.primary)
+			.or(self.files.first())
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthDependency {
+	// Authors can depend on a whole project or a specific version of it, so either of these may be missing
+	pub version_id: Option<String>,
+	pub project_id: Option<String>,
+	pub dependency_type: String, // required, optional, incompatible or embedded
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthVersionFile {
+	pub hashes: ModrinthHashes,
+	pub url: String,
+	pub filename: String,
+	pub primary: bool,
+	pub size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthHashes {
+	pub sha1: String,
+	pub sha512: String,
+}
+
+/// Key used for a loader in the `dependencies` map of `modrinth.index.json`
+pub fn modrinth_dependency_key(loader: &ModLoader) -> &'static str {
+	match loader {
+		ModLoader::Fabric => "fabric-loader",
+		ModLoader::Quilt => "quilt-loader",
+		ModLoader::Forge => "forge",
+		ModLoader::Neoforge => "neoforge",
+	}
+}
+
+/// Reverse of [`modrinth_dependency_key`]
+pub fn modrinth_loader_from_key(key: &str) -> Option<ModLoader> {
+	match key {
+		"fabric-loader" => Some(ModLoader::Fabric),
+		"quilt-loader" => Some(ModLoader::Quilt),
+		"forge" => Some(ModLoader::Forge),
+		"neoforge" => Some(ModLoader::Neoforge),
+		_ => None,
+	}
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ModrinthIndex {
+	#[serde(rename = "formatVersion")]
+	pub format_version: u8,
+	pub game: String,
+	#[serde(rename = "versionId")]
+	pub version_id: String,
+	pub name: String,
+	pub files: Vec<ModrinthIndexFile>,
+	pub dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ModrinthIndexFile {
+	pub path: String,
+	pub hashes: ModrinthHashes,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub env: Option<ModrinthEnv>,
+	pub downloads: Vec<String>,
+	#[serde(rename = "fileSize")]
+	pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModrinthEnv {
+	pub client: String,
+	pub server: String,
+}
+
+impl From<Sides> for ModrinthEnv {
+	fn from(sides: Sides) -> Self {
+		Self {
+			client: sides.client.to_string(),
+			server: sides.server.to_string(),
+		}
+	}
 }
@@ -0,0 +1,160 @@
+use {
+	crate::{ModVersions, http::HttpClient, version::MinecraftVersion},
+	chrono::{DateTime, Utc},
+	reqwest::Url,
+	serde::{Deserialize, Serialize},
+	std::{
+		cmp::Ordering,
+		collections::{BTreeMap, BTreeSet},
+	},
+};
+
+pub const API_BASE: &str = "https://piston-meta.mojang.com";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionManifest {
+	pub latest: LatestVersions,
+	pub versions: Vec<ManifestVersion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LatestVersions {
+	pub release: String,
+	pub snapshot: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ManifestVersion {
+	pub id: String,
+	#[serde(rename = "type")]
+	pub version_type: VersionType,
+	#[serde(rename = "releaseTime")]
+	pub release_time: DateTime<Utc>,
+	// Where the version's own json is, with the java version and downloads
+	#[serde(default)]
+	pub url: Option<String>,
+}
+
+/// The part of a version's own json modpackr uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionDetails {
+	// Versions from before mojang bundled java don't say
+	#[serde(rename = "javaVersion")]
+	pub java_version: Option<JavaVersion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaVersion {
+	#[serde(rename = "majorVersion")]
+	pub major_version: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+	Release,
+	// Mojang counts pre-releases and release candidates as snapshots too
+	Snapshot,
+	OldBeta,
+	OldAlpha,
+}
+
+pub fn get_version_manifest(api_base: &str) -> anyhow::Result<VersionManifest> {
+	let url = format!("{api_base}/mc/game/version_manifest_v2.json");
+
+	HttpClient::new().get_json(&url, &[])
+}
+
+/// Every minecraft version Mojang has published, no matter which loaders support it. The providers' version lists
+/// also contain things like `Client`, `Java 17` and `1.21-Snapshot`, this is what they get checked against
+#[derive(Debug, Clone, Default)]
+pub struct VersionCatalogue {
+	// Keyed by the lowercase id, curseforge doesn't keep the case of versions like `1.14 Pre-Release 1`
+	versions: BTreeMap<String, ManifestVersion>,
+	// Where the manifest came from, version jsons are fetched from there too
+	api_base: Option<String>,
+}
+
+impl From<VersionManifest> for VersionCatalogue {
+	fn from(manifest: VersionManifest) -> Self {
+		Self {
+			versions: manifest
+				.versions
+				.into_iter()
+				.map(|version| (version.id.to_lowercase(), version))
+				.collect(),
+			api_base: None,
+		}
+	}
+}
+
+impl VersionCatalogue {
+	pub fn fetch(api_base: &str) -> anyhow::Result<Self> {
+		Ok(Self {
+			api_base: Some(api_base.to_owned()),
+			..get_version_manifest(api_base)?.into()
+		})
+	}
+
+	pub fn get(&self, version: &str) -> Option<&ManifestVersion> {
+		self.versions.get(&version.to_lowercase())
+	}
+
+	/// Whether `version` is a full release. Mojang's type decides for the versions it has published, anything else goes
+	/// by its name
+	pub fn is_release(&self, version: &str) -> bool {
+		match self.get(version) {
+			Some(version) => version.version_type == VersionType::Release,
+			None => MinecraftVersion::new(version).is_release(),
+		}
+	}
+
+	/// Orders versions by when Mojang released them. Versions it hasn't published come first, in the order of their
+	/// names
+	pub fn compare(&self, a: &MinecraftVersion, b: &MinecraftVersion) -> Ordering {
+		let release_time = |version: &MinecraftVersion| {
+			self.get(version.as_str())
+				.map(|version| version.release_time)
+		};
+
+		release_time(a).cmp(&release_time(b)).then_with(|| a.cmp(b))
+	}
+
+	/// The Java version `version` needs, as its own json says. Versions Mojang hasn't published, or whose json doesn't
+	/// say, go by [`MinecraftVersion::java_version`]
+	pub fn java_version(&self, version: &str) -> anyhow::Result<u32> {
+		let Some(url) = self.get(version).and_then(|version| version.url.as_ref()) else {
+			return Ok(MinecraftVersion::new(version).java_version());
+		};
+		// The manifest links to mojang, but a fetched catalogue asks the same endpoint the manifest came from
+		let url = match self.api_base {
+			Some(ref api_base) => format!("{}{}", api_base, Url::parse(url)?.path()),
+			None => url.clone(),
+		};
+
+		let details: VersionDetails = HttpClient::new().get_json(&url, &[])?;
+
+		Ok(match details.java_version {
+			Some(java) => java.major_version,
+			None => MinecraftVersion::new(version).java_version(),
+		})
+	}
+
+	/// Drops everything that isn't a real minecraft version and spells the rest the way Mojang does, so versions
+	/// from different providers line up when they're intersected
+	pub fn normalize(&self, versions: ModVersions) -> ModVersions {
+		let normalize = |set: BTreeSet<MinecraftVersion>| {
+			set.into_iter()
+				.filter_map(|version| self.get(version.as_str()))
+				.map(|version| MinecraftVersion::new(version.id.as_str()))
+				.collect()
+		};
+
+		ModVersions {
+			fabric: normalize(versions.fabric),
+			forge: normalize(versions.forge),
+			neo_forge: normalize(versions.neo_forge),
+			quilt: normalize(versions.quilt),
+		}
+	}
+}
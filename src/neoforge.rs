@@ -1,3 +1,89 @@
-pub fn get_latest_neoforge_version<T: Into<String>>(_mc_version: T) -> anyhow::Result<String> {
-	todo!();
+use {
+	crate::http::HttpClient,
+	anyhow::anyhow,
+	quick_xml::{Reader, events::Event},
+};
+
+pub const MAVEN_BASE: &str = "https://maven.neoforged.net/releases";
+
+pub fn get_latest_neoforge_version<T: Into<String>>(
+	maven_base: &str,
+	mc_version: T,
+) -> anyhow::Result<String> {
+	let mc_version = mc_version.into();
+
+	// 1.20.1 was only ever published under the old forge artifact, as `1.20.1-47.1.x`
+	let (url, prefix) = if mc_version == "1.20.1" {
+		(
+			format!("{maven_base}/net/neoforged/forge/maven-metadata.xml"),
+			format!("{}-", mc_version),
+		)
+	} else {
+		(
+			format!("{maven_base}/net/neoforged/neoforge/maven-metadata.xml"),
+			neoforge_prefix(&mc_version)?,
+		)
+	};
+
+	let response = HttpClient::new().get_text(&url, &[])?;
+
+	let mut reader = Reader::from_str(&response);
+	reader.trim_text(true);
+
+	let mut versions = Vec::new();
+	let mut buf = Vec::new();
+
+	while let Ok(event) = reader.read_event_into(&mut buf) {
+		if let Event::Text(e) = event {
+			let text = e.unescape().unwrap_or_default().to_string();
+			if text.starts_with(&prefix) && !text.contains("alpha") && !text.contains("beta") {
+				versions.push(text);
+			}
+		} else if event == Event::Eof {
+			break;
+		}
+		buf.clear();
+	}
+
+	versions.sort_by(|a, b| natord::compare(a, b).reverse());
+
+	versions
+		.first()
+		.map(|s| {
+			format!(
+				"neoforge-{}",
+				s.strip_prefix(&format!("{}-", mc_version)).unwrap_or(s)
+			)
+		})
+		.ok_or(anyhow!(
+			"No stable neoforge versions for {} found in {}",
+			mc_version,
+			url
+		))
+}
+
+/// Where neoforge `version` is, both in the maven and in a server's libraries folder. 1.20.1 is under the old forge
+/// artifact like in [`get_latest_neoforge_version`]
+pub fn artifact_path(mc_version: &str, version: &str) -> String {
+	if mc_version == "1.20.1" {
+		format!("net/neoforged/forge/{mc_version}-{version}")
+	} else {
+		format!("net/neoforged/neoforge/{version}")
+	}
+}
+
+/// NeoForge drops the leading `1.` from the minecraft version, so 1.21.1 becomes `21.1.x` and 1.21 becomes `21.0.x`
+fn neoforge_prefix(mc_version: &str) -> anyhow::Result<String> {
+	let mut parts = mc_version
+		.strip_prefix("1.")
+		.ok_or(anyhow!("{} is not a version neoforge supports", mc_version))?
+		.split('.');
+
+	let major = parts
+		.next()
+		.filter(|part| !part.is_empty())
+		.ok_or(anyhow!("{} is not a version neoforge supports", mc_version))?;
+	let minor = parts.next().unwrap_or("0");
+
+	Ok(format!("{}.{}.", major, minor))
 }
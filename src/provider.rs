@@ -0,0 +1,380 @@
+use {
+	crate::{
+		Bridge, Endpoints, ModLoader, ModVersions, Sides,
+		curseforge::{self, CurseforgeClient, get_api_key},
+		modrinth::{self, ModrinthClient},
+	},
+	anyhow::anyhow,
+	serde::{Deserialize, Serialize},
+	sha1::{Digest, Sha1},
+	sha2::Sha512,
+	std::{collections::BTreeMap, fmt::Display, str::FromStr},
+};
+
+/// A project on one of the providers, this is what gets stored in modpack.ron
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub struct ProviderRef {
+	// The name of the provider this project is on, see ModProvider::name
+	pub provider: String,
+	pub id: String,
+	pub slug: String,
+	pub title: String,
+}
+
+/// How unstable a file may be, each channel also allows the ones before it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Channel {
+	#[default]
+	Release,
+	Beta,
+	Alpha,
+}
+
+impl Display for Channel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::Release => "release",
+				Self::Beta => "beta",
+				Self::Alpha => "alpha",
+			}
+		)
+	}
+}
+
+impl FromStr for Channel {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"release" => Ok(Self::Release),
+			"beta" => Ok(Self::Beta),
+			"alpha" => Ok(Self::Alpha),
+			_ => Err(anyhow!(
+				"Unknown channel {}, expected release, beta or alpha",
+				s
+			)),
+		}
+	}
+}
+
+/// Which of a project's files a mod may use
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileFilter {
+	pub channel: Channel,
+	// A pinned mod uses the files the pin allows, whatever their channel
+	pub pin: Option<Pin>,
+}
+
+impl FileFilter {
+	/// Whether file `file_id` on `provider` may be used. `names` are its version number and file name and
+	/// `game_versions` the minecraft versions it's for, see [`Pin::matches`]
+	pub fn allows(
+		&self,
+		provider: &str,
+		file_id: &str,
+		names: &[&str],
+		game_versions: &[String],
+		channel: Channel,
+	) -> bool {
+		match self.pin {
+			Some(ref pin) => pin.matches(provider, file_id, names, game_versions),
+			None => channel <= self.channel,
+		}
+	}
+}
+
+impl Display for FileFilter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.pin {
+			Some(ref pin) => write!(f, "matching {pin}"),
+			None => write!(f, "on the {} channel", self.channel),
+		}
+	}
+}
+
+/// Holds a mod at one file or version instead of the newest stable one
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, PartialOrd, Eq, Ord)]
+pub enum Pin {
+	// A modrinth version id or curseforge file id. Only that provider is used for the mod
+	File { provider: String, id: String },
+	// Files whose version number or file name has this in it, like `0.5.1f`. `*` matches anything, so `0.5.*` works
+	Version(String),
+}
+
+impl Pin {
+	/// Whether the pin allows file `file_id` on `provider`, `names` are its version number and file name like
+	/// `mc1.20.1-0.5.3` or `create-1.20.1-0.5.1f.jar`. The parts of the names that are one of `game_versions` are the
+	/// minecraft version rather than the mod's, so they're skipped, and only patterns with a `-`, `_`, `+` or space in
+	/// them are matched against the whole name
+	pub fn matches(
+		&self,
+		provider: &str,
+		file_id: &str,
+		names: &[&str],
+		game_versions: &[String],
+	) -> bool {
+		match self {
+			Self::File {
+				provider: pinned_provider,
+				id,
+			} => pinned_provider == provider && id == file_id,
+			Self::Version(pattern) => names.iter().any(|name| {
+				let name = name.strip_suffix(".jar").unwrap_or(name);
+				let separators = ['-', '_', '+', ' '];
+				let is_game_version = |part: &str| {
+					let part = part.strip_prefix("mc").unwrap_or(part);
+					game_versions
+						.iter()
+						.any(|version| version.eq_ignore_ascii_case(part))
+				};
+
+				pattern.contains(separators) && glob_matches(pattern, name) ||
+					name
+						.split(separators)
+						.filter(|part| !is_game_version(part))
+						.any(|part| glob_matches(pattern, part))
+			}),
+		}
+	}
+}
+
+/// Whether `text` matches `pattern`, where `*` matches anything
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+	match pattern.split_once('*') {
+		None => pattern == text,
+		Some((prefix, rest)) => text.strip_prefix(prefix).is_some_and(|text| {
+			(0..=text.len())
+				.filter(|i| text.is_char_boundary(*i))
+				.any(|i| glob_matches(rest, &text[i..]))
+		}),
+	}
+}
+
+impl Display for Pin {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::File { provider, id } => write!(f, "{provider}:{id}"),
+			Self::Version(pattern) => write!(f, "{pattern}"),
+		}
+	}
+}
+
+/// `modrinth:<version id>` or `curseforge:<file id>` pin a file, anything else is a version
+impl FromStr for Pin {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if s.is_empty() {
+			return Err(anyhow!("A pin needs a version or a file"));
+		}
+
+		match s.split_once(':') {
+			Some((provider, id)) if PROVIDERS.contains(&provider) => Ok(Self::File {
+				provider: provider.to_owned(),
+				id: id.to_owned(),
+			}),
+			_ => Ok(Self::Version(s.to_owned())),
+		}
+	}
+}
+
+/// A single downloadable file picked from a project
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ResolvedFile {
+	pub provider: String,
+	pub project_id: String,
+	// The modrinth version id or curseforge file id
+	pub file_id: String,
+	pub file_name: String,
+	// Hashes keyed by algorithm (sha1, sha512, md5)
+	pub hashes: BTreeMap<String, String>,
+	// Curseforge authors can disable third party downloads, so there isn't always one
+	pub url: Option<String>,
+	pub size: u64,
+	// Other projects on the same provider that this file declares a relationship with
+	#[serde(default)]
+	pub dependencies: Vec<Dependency>,
+	// The loader this file was made for, when it's only used through a bridge
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub bridged_from: Option<ModLoader>,
+	// Where the file runs, when the provider says
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sides: Option<Sides>,
+}
+
+impl ResolvedFile {
+	/// Checks downloaded contents against every hash of this file that modpackr can compute (sha1, sha512 and
+	/// curseforge's fingerprint)
+	pub fn verify(&self, contents: &[u8]) -> anyhow::Result<()> {
+		let mut checked = false;
+
+		for (algorithm, expected) in self.hashes.iter() {
+			let actual = match algorithm.as_str() {
+				"sha1" => hex::encode(Sha1::digest(contents)),
+				"sha512" => hex::encode(Sha512::digest(contents)),
+				"fingerprint" => curseforge::fingerprint(contents).to_string(),
+				_ => continue,
+			};
+
+			if !actual.eq_ignore_ascii_case(expected) {
+				return Err(anyhow!(
+					"{} doesn't match its {} hash, expected {} but got {}",
+					self.file_name,
+					algorithm,
+					expected,
+					actual
+				));
+			}
+			checked = true;
+		}
+
+		if !checked {
+			return Err(anyhow!(
+				"{} has no hash to check it against",
+				self.file_name
+			));
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Dependency {
+	// A project id on the same provider as the file that declares it
+	pub project_id: String,
+	pub kind: DependencyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DependencyKind {
+	Required,
+	Optional,
+	Incompatible,
+	// Shipped inside the file itself, so nothing needs to be installed
+	Embedded,
+}
+
+pub trait ModProvider {
+	/// The name that ProviderRef::provider uses for this provider
+	fn name(&self) -> &'static str;
+
+	/// Looks up a project from a link to it or its slug
+	fn resolve(&self, url_or_slug: &str) -> anyhow::Result<ProviderRef>;
+
+	/// Looks up a project by its id
+	fn project(&self, id: &str) -> anyhow::Result<ProviderRef>;
+
+	/// Every loader and minecraft version the project has a file for, only counting files `filter` allows
+	fn versions(&self, project: &ProviderRef, filter: &FileFilter) -> anyhow::Result<ModVersions>;
+
+	/// The newest file `filter` allows for the loader and minecraft version
+	fn select_file(
+		&self,
+		project: &ProviderRef,
+		loader: &ModLoader,
+		mc_version: &str,
+		filter: &FileFilter,
+	) -> anyhow::Result<ResolvedFile>;
+
+	/// Where a file picked by select_file runs. Modrinth only says this for the whole project, so it's a separate
+	/// request that only lock makes
+	fn sides(&self, _project: &ProviderRef, file: &ResolvedFile) -> anyhow::Result<Option<Sides>> {
+		Ok(file.sides)
+	}
+
+	/// Where a person can find the project, used when telling the user to install something manually
+	fn project_url(&self, project: &ProviderRef) -> String;
+}
+
+/// What select_file fails with when a project has nothing for the loader and version, as opposed to the provider
+/// not being reachable
+#[derive(Debug)]
+pub struct NoFile(pub String);
+
+impl Display for NoFile {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+impl std::error::Error for NoFile {}
+
+/// Names of every provider get_provider knows about, in the order they are preferred
+pub const PROVIDERS: [&str; 2] = [modrinth::PROVIDER, curseforge::PROVIDER];
+
+pub fn get_provider(name: &str, endpoints: &Endpoints) -> anyhow::Result<Box<dyn ModProvider>> {
+	match name {
+		modrinth::PROVIDER => Ok(Box::new(ModrinthClient::with_base_url(
+			endpoints.modrinth(),
+		))),
+		curseforge::PROVIDER => Ok(Box::new(CurseforgeClient::with_base_url(
+			get_api_key()?,
+			endpoints.curseforge(),
+		))),
+		_ => Err(anyhow!("Unknown provider {}", name)),
+	}
+}
+
+/// Creates providers the first time they're needed, so packs without curseforge mods don't need an API key
+pub struct Providers {
+	endpoints: Endpoints,
+	providers: BTreeMap<String, Box<dyn ModProvider>>,
+}
+
+impl Providers {
+	pub fn new(endpoints: &Endpoints) -> Self {
+		Self {
+			endpoints: endpoints.clone(),
+			providers: BTreeMap::new(),
+		}
+	}
+
+	pub fn get(&mut self, name: &str) -> anyhow::Result<&dyn ModProvider> {
+		if !self.providers.contains_key(name) {
+			self.providers
+				.insert(name.to_owned(), get_provider(name, &self.endpoints)?);
+		}
+
+		Ok(self.providers[name].as_ref())
+	}
+
+	/// The file `project` uses on `loader` and `mc_version`. If it has none, files for the loaders `bridges` let
+	/// `loader` use are tried next
+	pub fn select_file(
+		&mut self,
+		project: &ProviderRef,
+		loader: &ModLoader,
+		mc_version: &str,
+		bridges: &[Bridge],
+		filter: &FileFilter,
+	) -> anyhow::Result<ResolvedFile> {
+		let provider = self.get(&project.provider)?;
+
+		let native = provider.select_file(project, loader, mc_version, filter);
+		match &native {
+			Err(e) if e.is::<NoFile>() => {},
+			_ => return native,
+		}
+
+		for bridge in bridges
+			.iter()
+			.filter(|bridge| bridge.loader == *loader && bridge.applies(mc_version))
+		{
+			match provider.select_file(project, &bridge.accepts, mc_version, filter) {
+				Ok(mut file) => {
+					file.bridged_from = Some(bridge.accepts.clone());
+					return Ok(file);
+				},
+				Err(e) if e.is::<NoFile>() => {},
+				Err(e) => return Err(e),
+			}
+		}
+
+		native
+	}
+}
@@ -1,18 +1,36 @@
-use {reqwest::blocking::get, serde::Deserialize};
+use {crate::http::HttpClient, anyhow::anyhow, serde::Deserialize};
 
-const API_BASE: &str = "https://meta.quiltmc.org/v3";
+pub const API_BASE: &str = "https://meta.quiltmc.org/v3";
 
-pub fn get_latest_quilt_for_version<T: Into<String>>(version: T) -> anyhow::Result<String> {
+pub fn get_latest_quilt_for_version<T: Into<String>>(
+	api_base: &str,
+	version: T,
+) -> anyhow::Result<String> {
 	let version: String = version.into();
 
-	let url = format!("{API_BASE}/versions/loader/{}", version);
-	let response = get(&url)?.error_for_status()?;
-
-	let versions_response: Vec<LoaderVersion> = response.json()?;
+	let url = format!("{}/versions/loader/{}", api_base, version);
+	let versions_response: Vec<LoaderVersion> = HttpClient::new().get_json(&url, &[])?;
 
 	Ok(format!("quilt-{}", versions_response[0].loader.version))
 }
 
+/// The newest quilt installer, quilt servers are set up by running it rather than with a launcher jar
+pub fn get_latest_installer_url(api_base: &str) -> anyhow::Result<String> {
+	let url = format!("{}/versions/installer", api_base);
+	let installers: Vec<InstallerVersion> = HttpClient::new().get_json(&url, &[])?;
+
+	installers
+		.into_iter()
+		.next()
+		.map(|installer| installer.url)
+		.ok_or(anyhow!("No quilt installer found in {}", url))
+}
+
+#[derive(Deserialize)]
+struct InstallerVersion {
+	url: String,
+}
+
 #[derive(Deserialize)]
 struct LoaderVersion {
 	loader: Loader,
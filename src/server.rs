@@ -0,0 +1,194 @@
+use {
+	crate::{Endpoints, ModLoader, fabric, forge, neoforge, quilt, version::MinecraftVersion},
+	anyhow::anyhow,
+};
+
+/// Written as eula.txt, whoever runs the server has to agree to the EULA themselves
+pub const EULA: &str = "# Set this to true to agree to the Minecraft EULA (https://aka.ms/MinecraftEULA)\neula=false\n";
+
+/// How a server is started once its loader is installed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Launch {
+	// `java -jar <jar>`
+	Jar(String),
+	// `java @<dir>/unix_args.txt`, or win_args.txt on windows. This is how forge and neoforge start since 1.17
+	ArgsFile(String),
+}
+
+impl Launch {
+	/// The file the server starts from, which only exists once an installer has run
+	pub fn file(&self, windows: bool) -> String {
+		match self {
+			Self::Jar(jar) => jar.clone(),
+			Self::ArgsFile(dir) => {
+				format!("{dir}/{}_args.txt", if windows { "win" } else { "unix" })
+			},
+		}
+	}
+
+	fn args(&self, windows: bool) -> String {
+		match self {
+			Self::Jar(jar) => format!("-jar {jar}"),
+			Self::ArgsFile(_) => format!("@{}", self.file(windows)),
+		}
+	}
+}
+
+/// The jar a server needs for its loader, either a launcher that starts it or an installer that sets it up
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerLauncher {
+	pub url: String,
+	// What the jar is saved as in the server folder
+	pub file_name: String,
+	// Installers are run once with these before the first start, launchers don't have any
+	pub install_args: Vec<String>,
+	pub launch: Launch,
+}
+
+/// The launcher or installer for `loader_version`, which is the `<loader>-<version>` that `util::get_loader_version`
+/// returns
+pub fn server_launcher(
+	endpoints: &Endpoints,
+	loader: &ModLoader,
+	mc_version: &str,
+	loader_version: &str,
+) -> anyhow::Result<ServerLauncher> {
+	let version = loader_version
+		.strip_prefix(&format!("{loader}-"))
+		.ok_or(anyhow!("{} is not a {} version", loader_version, loader))?;
+
+	match loader {
+		ModLoader::Fabric => Ok(ServerLauncher {
+			url: fabric::get_server_launcher_url(&endpoints.fabric(), mc_version, version)?,
+			file_name: "fabric-server-launch.jar".to_owned(),
+			install_args: Vec::new(),
+			launch: Launch::Jar("fabric-server-launch.jar".to_owned()),
+		}),
+		ModLoader::Quilt => Ok(ServerLauncher {
+			url: quilt::get_latest_installer_url(&endpoints.quilt())?,
+			file_name: "quilt-installer.jar".to_owned(),
+			install_args: vec![
+				"install".to_owned(),
+				"server".to_owned(),
+				mc_version.to_owned(),
+				version.to_owned(),
+				"--download-server".to_owned(),
+				"--install-dir=.".to_owned(),
+			],
+			launch: Launch::Jar("quilt-server-launch.jar".to_owned()),
+		}),
+		ModLoader::Forge => {
+			let path = forge::artifact_path(mc_version, version);
+			// Before 1.17 the installer left a jar to start from instead of an args file
+			let launch = if MinecraftVersion::new(mc_version) < MinecraftVersion::new("1.17") {
+				Launch::Jar(format!("forge-{mc_version}-{version}.jar"))
+			} else {
+				Launch::ArgsFile(format!("libraries/{path}"))
+			};
+
+			Ok(installer(&endpoints.forge_maven(), &path, launch))
+		},
+		ModLoader::Neoforge => {
+			let path = neoforge::artifact_path(mc_version, version);
+			let launch = Launch::ArgsFile(format!("libraries/{path}"));
+
+			Ok(installer(&endpoints.neoforge_maven(), &path, launch))
+		},
+	}
+}
+
+/// A forge style installer at `path` in the maven, like `net/minecraftforge/forge/1.20.1-47.2.0`
+fn installer(maven_base: &str, path: &str, launch: Launch) -> ServerLauncher {
+	let mut parts = path.rsplit('/');
+	let version = parts.next().unwrap_or_default();
+	let artifact = parts.next().unwrap_or_default();
+	let file_name = format!("{artifact}-{version}-installer.jar");
+
+	ServerLauncher {
+		url: format!("{maven_base}/{path}/{file_name}"),
+		file_name,
+		install_args: vec!["--installServer".to_owned()],
+		launch,
+	}
+}
+
+impl ServerLauncher {
+	/// start.sh, which installs the loader if it has to and starts the server with Java `java`, the version minecraft
+	/// `mc_version` needs
+	pub fn start_sh(&self, mc_version: &str, java: u32) -> String {
+		let mut script = format!(
+			r#"#!/usr/bin/env sh
+# Minecraft {mc_version} needs Java {java}, set JAVA to its java binary if that isn't the default one
+cd "$(dirname "$0")"
+JAVA="${{JAVA:-java}}"
+MEMORY="${{MEMORY:-4G}}"
+
+if ! "$JAVA" -version 2>&1 | grep -q 'version "{prefix}'; then
+	echo "Minecraft {mc_version} needs Java {java}, set JAVA to its java binary if the server doesn't start"
+fi
+"#,
+			prefix = java_version_prefix(java)
+		);
+
+		if !self.install_args.is_empty() {
+			script.push_str(&format!(
+				r#"
+if [ ! -f {file} ]; then
+	"$JAVA" -jar {installer} {args} || exit 1
+fi
+"#,
+				file = self.launch.file(false),
+				installer = self.file_name,
+				args = self.install_args.join(" ")
+			));
+		}
+
+		script.push_str(&format!(
+			"\nexec \"$JAVA\" -Xmx$MEMORY {} nogui \"$@\"\n",
+			self.launch.args(false)
+		));
+
+		script
+	}
+
+	/// start.bat, the windows version of [`Self::start_sh`]
+	pub fn start_bat(&self, mc_version: &str, java: u32) -> String {
+		let mut script = format!(
+			"@echo off\r\n\
+			rem Minecraft {mc_version} needs Java {java}, set JAVA to its java.exe if that isn't the default one\r\n\
+			cd /d \"%~dp0\"\r\n\
+			if not defined JAVA set JAVA=java\r\n\
+			if not defined MEMORY set MEMORY=4G\r\n\
+			\r\n\
+			\"%JAVA%\" -version 2>&1 | findstr /c:\"version \\\"{prefix}\" >nul || echo Minecraft {mc_version} needs Java {java}, set JAVA to its java.exe if the server doesn't start\r\n",
+			prefix = java_version_prefix(java)
+		);
+
+		if !self.install_args.is_empty() {
+			script.push_str(&format!(
+				"\r\nif not exist {file} (\r\n\
+				\t\"%JAVA%\" -jar {installer} {args} || exit /b 1\r\n\
+				)\r\n",
+				file = self.launch.file(true).replace('/', "\\"),
+				installer = self.file_name,
+				args = self.install_args.join(" ")
+			));
+		}
+
+		script.push_str(&format!(
+			"\r\n\"%JAVA%\" -Xmx%MEMORY% {} nogui %*\r\npause\r\n",
+			self.launch.args(true)
+		));
+
+		script
+	}
+}
+
+/// What `java -version` starts the version number with, Java 8 still calls itself 1.8
+fn java_version_prefix(java: u32) -> String {
+	if java == 8 {
+		"1.8".to_owned()
+	} else {
+		java.to_string()
+	}
+}
@@ -1,78 +1,130 @@
 use {
 	crate::{
-		Config, Mod, ModLoader, ModVersions, Modpack,
+		CONFIG_SCHEMA_VERSION, Config, Endpoints, ExportFormat, LockedMod, Lockfile, ManualJar,
+		Mod, ModLoader, ModVersions, Modpack, OverrideFilter, Side, Sides, Solver, Strategy,
 		curseforge::{
-			CurseforgeClient, CurseforgeManifest, CurseforgeMinecraftManifest,
-			CurseforgeModLoaderEntry, get_api_key,
+			self, CurseforgeClient, CurseforgeManifest, CurseforgeManifestFile,
+			CurseforgeMinecraftManifest, CurseforgeModLoaderEntry, get_api_key,
 		},
+		diagnostics::{LoaderDiagnostic, RankedTarget, diagnose, rank_targets},
+		download::{Downloader, Store},
 		fabric::get_stable_fabric_for_version,
 		forge::get_latest_forge_version,
-		modrinth::{get_modrinth_mod_from_url, get_versions_from_modrinth},
+		http::HttpClient,
+		modrinth::{
+			self, ModrinthClient, ModrinthHashes, ModrinthIndex, ModrinthIndexFile,
+			modrinth_dependency_key, modrinth_loader_from_key,
+		},
+		mojang::VersionCatalogue,
 		neoforge::get_latest_neoforge_version,
+		provider::{DependencyKind, NoFile, Pin, Providers, ResolvedFile},
 		quilt::get_latest_quilt_for_version,
+		server::{EULA, server_launcher},
+		version::MinecraftVersion,
 	},
 	anyhow::anyhow,
-	std::{collections::BTreeMap, env::current_dir, fs, io::Write, path::Path},
+	sha1::{Digest, Sha1},
+	std::{
+		collections::{BTreeMap, BTreeSet},
+		env::current_dir,
+		fs,
+		io::{self, Write},
+		path::{Component, Path, PathBuf},
+	},
 	zip::{
-		ZipWriter,
+		ZipArchive, ZipWriter,
 		write::{ExtendedFileOptions, FileOptions},
 	},
 };
 
 pub fn get_loader_version<T: Into<String>>(
+	endpoints: &Endpoints,
 	loader: ModLoader,
 	version: T,
 ) -> anyhow::Result<String> {
 	match loader {
-		ModLoader::Fabric => get_stable_fabric_for_version(version),
-		ModLoader::Quilt => get_latest_quilt_for_version(version),
-		ModLoader::Forge => get_latest_forge_version(version),
-		ModLoader::Neoforge => get_latest_neoforge_version(version),
+		ModLoader::Fabric => get_stable_fabric_for_version(&endpoints.fabric(), version),
+		ModLoader::Quilt => get_latest_quilt_for_version(&endpoints.quilt(), version),
+		ModLoader::Forge => get_latest_forge_version(&endpoints.forge_maven(), version),
+		ModLoader::Neoforge => get_latest_neoforge_version(&endpoints.neoforge_maven(), version),
 	}
 }
 
-pub fn find_compatible(
+/// Reverse of [`get_loader_version`], splits a curseforge style loader id like `forge-47.2.0` into the loader and
+/// its version
+pub fn parse_loader_version(id: &str) -> anyhow::Result<(ModLoader, String)> {
+	let (loader, version) = id
+		.split_once('-')
+		.ok_or(anyhow!("{} is not a valid loader id", id))?;
+
+	let loader = match loader {
+		"fabric" => ModLoader::Fabric,
+		"quilt" => ModLoader::Quilt,
+		"forge" => ModLoader::Forge,
+		"neoforge" => ModLoader::Neoforge,
+		_ => return Err(anyhow!("Unknown mod loader {}", loader)),
+	};
+
+	Ok((loader, version.to_owned()))
+}
+
+/// Every loader and minecraft version all mods support, best first according to `config.loaders()` and
+/// `config.strategy`. Mods count for the loaders `config.bridges` let use them. Unless snapshots are allowed only full
+/// releases are considered, a forced version is used as is. `catalogue` decides what's a release and which version
+/// is newer
+pub fn compatible_targets(
 	versions: &[ModVersions],
-	force_loader: Option<ModLoader>,
-	force_version: Option<&str>,
-) -> Option<(ModLoader, String)> {
+	config: &Config,
+	catalogue: &VersionCatalogue,
+) -> Vec<(ModLoader, String)> {
 	if versions.is_empty() {
-		return None;
+		return Vec::new();
 	}
 
-	let mut intersection = versions[0].clone();
+	let mut intersection = versions[0].bridged(&config.bridges);
 	for v in &versions[1..] {
-		intersection -= v.clone();
+		intersection -= v.bridged(&config.bridges);
 	}
 
-	let loader_map = BTreeMap::from([
-		(ModLoader::Quilt, &intersection.quilt),
-		(ModLoader::Fabric, &intersection.fabric),
-		(ModLoader::Neoforge, &intersection.neo_forge),
-		(ModLoader::Forge, &intersection.forge),
-	]);
-
-	for (loader, versions) in loader_map {
-		if versions.is_empty() {
-			continue;
-		}
+	let mut targets = Vec::new();
+	for loader in config.loaders() {
+		let mut supported = intersection
+			.get(&loader)
+			.iter()
+			.filter(|version| match config.version.as_deref() {
+				Some(force_version) => **version == MinecraftVersion::new(force_version),
+				None => config.allow_snapshots || catalogue.is_release(version.as_str()),
+			})
+			.collect::<Vec<_>>();
+		supported.sort_by(|a, b| catalogue.compare(b, a));
 
-		if let Some(ref force_loader) = force_loader {
-			if loader != *force_loader {
-				continue;
-			}
-		}
+		targets.extend(
+			supported
+				.into_iter()
+				.map(|version| (loader.clone(), version)),
+		);
+	}
 
-		if let Some(force_version) = force_version {
-			if versions.contains(&force_version.to_owned()) {
-				return Some((loader, force_version.to_owned()));
-			}
-		} else {
-			return Some((loader, versions.last().unwrap().to_string()));
-		}
+	if config.strategy == Strategy::NewestFirst {
+		// Stable, so loaders supporting the same version stay in order
+		targets.sort_by(|(_, a), (_, b)| catalogue.compare(b, a));
 	}
 
-	None
+	targets
+		.into_iter()
+		.map(|(loader, version)| (loader, version.to_string()))
+		.collect()
+}
+
+/// The best of [`compatible_targets`], if there are any
+pub fn find_compatible(
+	versions: &[ModVersions],
+	config: &Config,
+	catalogue: &VersionCatalogue,
+) -> Option<(ModLoader, String)> {
+	compatible_targets(versions, config, catalogue)
+		.into_iter()
+		.next()
 }
 
 pub fn load_versions(project_dir: &Path) -> anyhow::Result<Vec<ModVersions>> {
@@ -82,7 +134,7 @@ pub fn load_versions(project_dir: &Path) -> anyhow::Result<Vec<ModVersions>> {
 		let entry = entry.unwrap();
 		let path = entry.path();
 
-		if path.extension().map_or(false, |ext| ext == "ron") {
+		if path.extension().is_some_and(|ext| ext == "ron") {
 			let contents = fs::read_to_string(&path)?;
 			let versions: ModVersions = ron::from_str(&contents)?;
 			results.push(versions);
@@ -92,12 +144,63 @@ pub fn load_versions(project_dir: &Path) -> anyhow::Result<Vec<ModVersions>> {
 	Ok(results)
 }
 
+/// Where `check` keeps the versions of the mod called `name`. Names of auto-added mods come from the providers, so
+/// anything that can't be in a file name is replaced
+pub fn versions_path(project_dir: &Path, name: &str) -> PathBuf {
+	let file_name = name
+		.chars()
+		.map(|c| match c {
+			'/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>();
+
+	project_dir.join("mods").join(format!("{file_name}.ron"))
+}
+
+/// Like [`load_versions`], keyed by mod name and only for `mods`. Mods removed from modpack.ron leave their file in
+/// mods/ behind, those are skipped
+pub fn load_named_versions(
+	project_dir: &Path,
+	mods: &[Mod],
+) -> anyhow::Result<BTreeMap<String, ModVersions>> {
+	let mut results = BTreeMap::new();
+
+	for entry in mods {
+		let path = versions_path(project_dir, &entry.name);
+
+		if path.is_file() {
+			let contents = fs::read_to_string(&path)?;
+			results.insert(entry.name.clone(), ron::from_str(&contents)?);
+		}
+	}
+
+	Ok(results)
+}
+
 pub fn load_config(project_dir: &Path) -> anyhow::Result<Config> {
 	let toml = project_dir.join("config.toml");
 
 	if toml.exists() {
 		let contents = fs::read_to_string(toml)?;
-		Ok(toml::from_str(&contents)?)
+		let mut config: Config = toml::from_str(&contents)?;
+
+		if config.schema_version > CONFIG_SCHEMA_VERSION {
+			return Err(anyhow!(
+				"config.toml uses schema version {}, but this version of modpackr only understands up to {}",
+				config.schema_version,
+				CONFIG_SCHEMA_VERSION
+			));
+		}
+
+		// Nothing has changed shape yet, older configs only need their version bumped
+		if config.schema_version < CONFIG_SCHEMA_VERSION {
+			config.schema_version = CONFIG_SCHEMA_VERSION;
+			update_config(project_dir, &config)?;
+		}
+
+		Ok(config)
 	} else {
 		Err(anyhow!(
 			"This directory has not been initialized, use `modpack init` to initialize or use `modpack new <name>` to create a new modpack with the name specified"
@@ -105,12 +208,24 @@ pub fn load_config(project_dir: &Path) -> anyhow::Result<Config> {
 	}
 }
 
+pub fn update_config(project_dir: &Path, config: &Config) -> anyhow::Result<()> {
+	let toml = toml::ser::to_string_pretty(config)?;
+
+	Ok(fs::write(project_dir.join("config.toml"), toml)?)
+}
+
 pub fn load_modpack(project_dir: &Path) -> anyhow::Result<Modpack> {
 	let ron = project_dir.join("modpack.ron");
 
 	if ron.exists() {
 		let contents = fs::read_to_string(ron)?;
-		Ok(ron::from_str(&contents)?)
+		let mut modpack: Modpack = ron::from_str(&contents)?;
+
+		for m in modpack.mods.iter_mut() {
+			m.migrate();
+		}
+
+		Ok(modpack)
 	} else {
 		Err(anyhow!(
 			"modpack.ron was not found, use `modpack init` to initialize this directory or use `modpack new <name>` to create a new modpack with the name specified"
@@ -135,7 +250,9 @@ pub fn initialize_project() -> anyhow::Result<()> {
 
 pub fn create_project_at_path(path: &Path) -> anyhow::Result<()> {
 	fs::create_dir_all(path)?;
-	fs::create_dir_all(path.join("mods"))?;
+	for dir in ["mods", "overrides", "client-overrides", "server-overrides"] {
+		fs::create_dir_all(path.join(dir))?;
+	}
 
 	let modpack = Modpack {
 		name: path
@@ -149,152 +266,693 @@ pub fn create_project_at_path(path: &Path) -> anyhow::Result<()> {
 
 	update_modpack(path, modpack)?;
 
-	let config = Config::default();
-	let toml = toml::ser::to_string_pretty(&config)?;
-	fs::write(path.join("config.toml"), toml)?;
+	update_config(path, &Config::default())?;
 
 	Ok(())
 }
 
+/// Adds a mod to modpack.ron. A manual mod's `jar` comes from [`bundle_jar`], so if the mod can't be added the jar is
+/// taken out of `jars/` again rather than left behind without a mod
 pub fn add_mod<T: Into<String>, U: Into<String>, V: Into<String>>(
 	project_dir: &Path,
 	mod_name: T,
 	curseforge: Option<U>,
 	modrinth: Option<V>,
 	manual: bool,
+	jar: Option<ManualJar>,
+) -> anyhow::Result<Mod> {
+	let file_name = jar.as_ref().map(|jar| jar.file_name.clone());
+	let added = insert_mod(
+		project_dir,
+		mod_name.into(),
+		curseforge.map(Into::into),
+		modrinth.map(Into::into),
+		manual,
+		jar,
+	);
+
+	// Another mod's jar with the same name stays where it is
+	if added.is_err() &&
+		let Some(file_name) = file_name &&
+		load_modpack(project_dir)
+			.is_ok_and(|modpack| check_jar_name(&modpack, &file_name).is_ok())
+	{
+		// The error from adding the mod is the one worth reporting
+		let _ = fs::remove_file(project_dir.join("jars").join(file_name));
+	}
+
+	added
+}
+
+fn insert_mod(
+	project_dir: &Path,
+	mod_name: String,
+	curseforge: Option<String>,
+	modrinth: Option<String>,
+	manual: bool,
+	jar: Option<ManualJar>,
 ) -> anyhow::Result<Mod> {
-	if curseforge.is_some() && manual ||
-		modrinth.is_some() && manual ||
+	if manual && (curseforge.is_some() || modrinth.is_some()) ||
 		(curseforge.is_none() && modrinth.is_none() && !manual)
 	{
 		return Err(anyhow!(
 			"You must specify what kind of mod this is. to use a curseforge link, use -c <link>, for modrinth -m <link> and if it is on neither of those, specify it as a manual mod with -n"
 		));
 	}
+	if jar.is_some() && !manual {
+		return Err(anyhow!(
+			"Only manual mods can come with a jar, the others are downloaded from their provider"
+		));
+	}
 
+	let config = load_config(project_dir)?;
 	let mut modpack = load_modpack(project_dir)?;
+	check_mod_name(&modpack, &mod_name)?;
+	if let Some(ref jar) = jar {
+		check_jar_name(&modpack, &jar.file_name)?;
+	}
 
-	let curseforge = if let Some(curseforge) = curseforge {
-		let curseforge_client = CurseforgeClient::new(get_api_key()?);
-		let curseforge_mod_data = curseforge_client.from_url(curseforge.into())?;
+	let mut providers = Providers::new(&config.endpoints);
+	let sources = [
+		(modrinth::PROVIDER, modrinth),
+		(curseforge::PROVIDER, curseforge),
+	];
 
-		Some(curseforge_mod_data)
-	} else {
-		None
+	let mut projects = Vec::new();
+	for (provider, url) in sources {
+		if let Some(url) = url {
+			projects.push(providers.get(provider)?.resolve(&url)?);
+		}
+	}
+
+	let mod_data = Mod {
+		jar,
+		..Mod::new(mod_name, projects)
 	};
 
-	let modrinth = if let Some(modrinth) = modrinth {
-		let modrinth_mod_data = get_modrinth_mod_from_url(modrinth.into())?;
+	modpack.mods.push(mod_data.clone());
 
-		Some(modrinth_mod_data)
+	update_modpack(project_dir, modpack)?;
+
+	Ok(mod_data)
+}
+
+/// Where a manual mod's jar comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum JarSource {
+	Path(PathBuf),
+	// A direct download, checked against a sha1 or sha512 hash
+	Url { url: String, hash: String },
+}
+
+impl JarSource {
+	/// Sources starting with `http://` or `https://` are downloaded and need a hash, anything else is a local file
+	pub fn new<T: Into<String>>(source: T, hash: Option<String>) -> anyhow::Result<Self> {
+		let source = source.into();
+
+		if source.starts_with("http://") || source.starts_with("https://") {
+			let hash = hash.ok_or(anyhow!(
+				"{} needs a sha1 or sha512 hash to check the download against",
+				source
+			))?;
+			Ok(Self::Url { url: source, hash })
+		} else {
+			Ok(Self::Path(source.into()))
+		}
+	}
+}
+
+/// Mods are looked up by name everywhere, from mods/*.ron to the lockfile, so no two mods can share one
+fn check_mod_name(modpack: &Modpack, name: &str) -> anyhow::Result<()> {
+	if modpack.mods.iter().any(|m| m.name == name) {
+		Err(anyhow!(
+			"There already is a mod called {} in modpack.ron",
+			name
+		))
 	} else {
-		None
-	};
+		Ok(())
+	}
+}
 
-	let name = mod_name.into();
+/// Exports put every jar in `mods/` by its file name, so two manual mods can't bring jars with the same name
+fn check_jar_name(modpack: &Modpack, file_name: &str) -> anyhow::Result<()> {
+	match modpack
+		.mods
+		.iter()
+		.find(|m| m.jar.as_ref().is_some_and(|jar| jar.file_name == file_name))
+	{
+		Some(other) => Err(anyhow!(
+			"{} already comes with a jar called {}",
+			other.name,
+			file_name
+		)),
+		None => Ok(()),
+	}
+}
 
-	let mod_data = Mod {
-		name: name.clone(),
-		curseforge,
-		modrinth,
+/// Copies or downloads a manual mod's jar into the project's `jars/` folder. It isn't marked redistributable, only the
+/// user can say whether the mod's license allows that
+pub fn bundle_jar(project_dir: &Path, source: &JarSource) -> anyhow::Result<ManualJar> {
+	let (file_name, contents, url) = match source {
+		JarSource::Path(path) => {
+			let file_name = path
+				.file_name()
+				.and_then(|os_str| os_str.to_str())
+				.ok_or(anyhow!("{} is not a file", path.display()))?;
+			let contents =
+				fs::read(path).map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+
+			(file_name.to_owned(), contents, None)
+		},
+		JarSource::Url { url, hash } => {
+			let algorithm = match hash.len() {
+				40 => "sha1",
+				128 => "sha512",
+				_ => {
+					return Err(anyhow!(
+						"Unknown hash {}, expected a sha1 or sha512 hash",
+						hash
+					));
+				},
+			};
+			let file_name = url
+				.split(['?', '#'])
+				.next()
+				.and_then(|url| url.rsplit('/').next())
+				.filter(|name| !name.is_empty())
+				.ok_or(anyhow!("Can't tell the file name from {}", url))?
+				.to_owned();
+
+			// Fetching through the store checks the hash and keeps a copy like any other download
+			let file = ResolvedFile {
+				file_name: file_name.clone(),
+				hashes: BTreeMap::from([(algorithm.to_owned(), hash.clone())]),
+				url: Some(url.clone()),
+				..Default::default()
+			};
+			let downloader =
+				Downloader::new(Store::open()?, load_config(project_dir)?.parallel_downloads);
+			let contents = fs::read(downloader.fetch(&file)?)?;
+
+			(file_name, contents, Some(url.clone()))
+		},
 	};
+	check_jar_name(&load_modpack(project_dir)?, &file_name)?;
 
-	modpack.mods.push(mod_data.clone());
+	let jars = project_dir.join("jars");
+	fs::create_dir_all(&jars)?;
+	fs::write(jars.join(&file_name), &contents)?;
+
+	Ok(ManualJar {
+		file_name,
+		sha1: hex::encode(Sha1::digest(&contents)),
+		url,
+		redistributable: false,
+	})
+}
+
+/// Applies `edit` to the mod called `name` in modpack.ron and saves it
+pub fn edit_mod(
+	project_dir: &Path,
+	name: &str,
+	edit: impl FnOnce(&mut Mod),
+) -> anyhow::Result<Mod> {
+	let mut modpack = load_modpack(project_dir)?;
+
+	let entry = modpack
+		.mods
+		.iter_mut()
+		.find(|m| m.name == name)
+		.ok_or(anyhow!("There is no mod called {} in modpack.ron", name))?;
+	edit(entry);
+	let entry = entry.clone();
 
 	update_modpack(project_dir, modpack)?;
 
-	Ok(mod_data)
+	Ok(entry)
+}
+
+/// Pins the mod called `name` to `pin`, or unpins it if `pin` is None
+pub fn pin_mod(project_dir: &Path, name: &str, pin: Option<Pin>) -> anyhow::Result<Mod> {
+	let modpack = load_modpack(project_dir)?;
+
+	if let Some(Pin::File { ref provider, .. }) = pin &&
+		let Some(entry) = modpack.mods.iter().find(|m| m.name == name) &&
+		entry.provider(provider).is_none()
+	{
+		return Err(anyhow!("{} is not on {}", name, provider));
+	}
+
+	edit_mod(project_dir, name, |m| m.pin = pin)
 }
 
-pub fn check(project_dir: &Path) -> anyhow::Result<(Option<(ModLoader, String)>, Vec<String>)> {
-	let _config = load_config(project_dir)?;
+/// What `check` found out about the pack
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckResult {
+	// The loader and minecraft version every mod supports, if there is one
+	pub target: Option<(ModLoader, String)>,
+	// Every loader and minecraft version that works, best first, so the first one is the target
+	pub compatible: Vec<(ModLoader, String)>,
+	// Names of mods without a provider
+	pub manual_mods: Vec<String>,
+	// Mods that were added because another mod requires them, as (added mod, mod that requires it)
+	pub added_dependencies: Vec<(String, String)>,
+	// Pairs of mods in the pack where the first declares it doesn't work with the second
+	pub conflicts: Vec<(String, String)>,
+	// Mods that only work on the target through a bridge, as (mod, loader its file is for)
+	pub bridged: Vec<(String, ModLoader)>,
+	// When there's no target, what stands in the way on each loader
+	pub diagnostics: Vec<LoaderDiagnostic>,
+	// With the coverage solver, the targets that support the most of the pack, best first
+	pub ranking: Vec<RankedTarget>,
+}
+
+pub fn check(project_dir: &Path) -> anyhow::Result<CheckResult> {
+	let config = load_config(project_dir)?;
 	let mut modpack = load_modpack(project_dir)?;
-	let curseforge_client = CurseforgeClient::new(get_api_key()?);
-	let mut manual_mods = Vec::new();
-	for entry in modpack.mods.iter() {
-		if entry.modrinth.is_none() && entry.curseforge.is_none() {
-			manual_mods.push(entry.name.clone());
-			continue;
+	let mut providers = Providers::new(&config.endpoints);
+	let catalogue = VersionCatalogue::fetch(&config.endpoints.mojang())?;
+	let mut result = CheckResult::default();
+	let mut checked = BTreeSet::new();
+
+	// Adding a dependency can change which version is compatible, which can change what the mods depend on, so this
+	// keeps going until nothing new is added
+	loop {
+		for entry in modpack.mods.iter() {
+			if !checked.insert(entry.name.clone()) {
+				continue;
+			}
+
+			if entry.is_manual() {
+				result.manual_mods.push(entry.name.clone());
+				continue;
+			}
+
+			let mut versions = ModVersions::default();
+			for project in entry.providers.iter() {
+				versions += providers
+					.get(&project.provider)?
+					.versions(project, &entry.file_filter(config.channel))?;
+			}
+			let versions = catalogue.normalize(versions);
+
+			let ron_string =
+				ron::ser::to_string_pretty(&versions, ron::ser::PrettyConfig::default())?;
+			fs::write(versions_path(project_dir, &entry.name), ron_string)?;
 		}
 
-		let curseforge_versions = if entry.curseforge.is_some() {
-			curseforge_client.get_versions(entry.curseforge.clone().unwrap().id)?
-		} else {
-			ModVersions::default()
-		};
+		let versions_vec = load_named_versions(project_dir, &modpack.mods)?
+			.into_values()
+			.collect::<Vec<_>>();
+		result.compatible = compatible_targets(&versions_vec, &config, &catalogue);
+		result.target = result.compatible.first().cloned();
 
-		let modrinth_versions = if entry.modrinth.is_some() {
-			get_versions_from_modrinth(entry.modrinth.clone().unwrap().id)?
-		} else {
-			ModVersions::default()
+		let Some((loader, version)) = &result.target else {
+			break;
 		};
 
-		let versions = modrinth_versions + curseforge_versions;
+		let files = select_files(&modpack, &mut providers, loader, version, &config)?;
+		let dependencies = missing_dependencies(&modpack, &files, &mut providers)?;
+		if dependencies.is_empty() {
+			result.conflicts = find_conflicts(&modpack, &files, &mut providers);
+			result.bridged = bridged_mods(&modpack, &files);
+			break;
+		}
 
-		let ron_string = ron::ser::to_string_pretty(&versions, ron::ser::PrettyConfig::default())?;
-		fs::write(
-			project_dir.join("mods").join(format!("{}.ron", entry.name)),
-			ron_string,
-		)?;
+		for dependency in dependencies {
+			result.added_dependencies.push((
+				dependency.name.clone(),
+				dependency.required_by.clone().unwrap_or_default(),
+			));
+			modpack.mods.push(dependency);
+		}
 	}
 
-	let versions_vec = load_versions(project_dir)?;
-	let result = find_compatible(&versions_vec, None, None);
-	if let Some((loader, version)) = result {
+	let named_versions = load_named_versions(project_dir, &modpack.mods)?
+		.into_iter()
+		.map(|(name, versions)| (name, versions.bridged(&config.bridges)))
+		.collect();
+	if let Some((loader, version)) = &result.target {
 		modpack.loader = Some(loader.clone());
 		modpack.minecraft_version = Some(version.clone());
-
+	} else {
+		result.diagnostics = diagnose(
+			&named_versions,
+			&catalogue,
+			&config.loaders(),
+			config.version.as_deref(),
+			config.allow_snapshots,
+		);
+	}
+	if config.solver == Solver::Coverage {
+		result.ranking = rank_targets(
+			&named_versions,
+			&modpack.mods,
+			&catalogue,
+			&config.loaders(),
+			config.version.as_deref(),
+			config.allow_snapshots,
+		);
+	}
+	if result.target.is_some() || !result.added_dependencies.is_empty() {
 		update_modpack(project_dir, modpack)?;
-		Ok((Some((loader, version)), manual_mods))
+	}
+
+	Ok(result)
+}
+
+/// The file every provider would pick for each mod on `loader` and `mc_version`. A mod only has to be on one provider
+/// for a version, so providers without a file are skipped, but a provider that can't be reached is an error
+fn select_files<'a>(
+	modpack: &'a Modpack,
+	providers: &mut Providers,
+	loader: &ModLoader,
+	mc_version: &str,
+	config: &Config,
+) -> anyhow::Result<Vec<(&'a Mod, ResolvedFile)>> {
+	let mut files = Vec::new();
+
+	for entry in modpack.mods.iter() {
+		let filter = entry.file_filter(config.channel);
+
+		for project in entry.providers.iter() {
+			match providers.select_file(project, loader, mc_version, &config.bridges, &filter) {
+				Ok(file) => files.push((entry, file)),
+				Err(e) if e.is::<NoFile>() => {},
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	Ok(files)
+}
+
+/// Mods where every file in `files` is used through a bridge, with the loader the first one was made for
+fn bridged_mods(modpack: &Modpack, files: &[(&Mod, ResolvedFile)]) -> Vec<(String, ModLoader)> {
+	modpack
+		.mods
+		.iter()
+		.filter_map(|entry| {
+			let mut files = files
+				.iter()
+				.filter(|(m, _)| m.name == entry.name)
+				.map(|(_, file)| file.bridged_from.clone());
+
+			let first = files.next()??;
+			files
+				.all(|bridged_from| bridged_from.is_some())
+				.then(|| (entry.name.clone(), first))
+		})
+		.collect()
+}
+
+/// Required dependencies of `files` that aren't in the pack yet, as new mods that remember which mod needed them
+fn missing_dependencies(
+	modpack: &Modpack,
+	files: &[(&Mod, ResolvedFile)],
+	providers: &mut Providers,
+) -> anyhow::Result<Vec<Mod>> {
+	let mut missing: Vec<Mod> = Vec::new();
+
+	for (entry, file) in files {
+		for dependency in file
+			.dependencies
+			.iter()
+			.filter(|dependency| dependency.kind == DependencyKind::Required)
+		{
+			let known = |m: &Mod| {
+				m.provider(&file.provider)
+					.is_some_and(|p| p.id == dependency.project_id)
+			};
+			if modpack.mods.iter().chain(missing.iter()).any(known) {
+				continue;
+			}
+
+			// The same mod can already be in the pack from the other provider, names are unique so one with the same
+			// name is taken to be it
+			let dependency = providers
+				.get(&file.provider)?
+				.project(&dependency.project_id)?;
+			if modpack.mods.iter().chain(missing.iter()).any(|m| {
+				m.is_project(&dependency) || m.name.eq_ignore_ascii_case(&dependency.title)
+			}) {
+				continue;
+			}
+
+			let mut new_mod = Mod::new(dependency.title.clone(), vec![dependency]);
+			new_mod.required_by = Some(entry.name.clone());
+			missing.push(new_mod);
+		}
+	}
+
+	Ok(missing)
+}
+
+/// Pairs of mods in the pack where one of `files` declares it's incompatible with the other, each pair is only
+/// reported once
+fn find_conflicts(
+	modpack: &Modpack,
+	files: &[(&Mod, ResolvedFile)],
+	providers: &mut Providers,
+) -> Vec<(String, String)> {
+	let mut conflicts: Vec<(String, String)> = Vec::new();
+
+	for (entry, file) in files {
+		for dependency in file
+			.dependencies
+			.iter()
+			.filter(|dependency| dependency.kind == DependencyKind::Incompatible)
+		{
+			let by_id = modpack.mods.iter().find(|m| {
+				m.provider(&file.provider)
+					.is_some_and(|p| p.id == dependency.project_id)
+			});
+
+			// Otherwise the mod can be in the pack from the other provider, or as a manual mod with the same name. That
+			// takes looking the project up, which is pointless if every mod is on this provider. If the lookup fails
+			// there's nothing to compare against, so it isn't a conflict
+			let other = by_id.or_else(|| {
+				if modpack
+					.mods
+					.iter()
+					.all(|m| m.provider(&file.provider).is_some())
+				{
+					return None;
+				}
+
+				let project = providers
+					.get(&file.provider)
+					.and_then(|provider| provider.project(&dependency.project_id))
+					.ok()?;
+
+				modpack
+					.mods
+					.iter()
+					.find(|m| m.is_project(&project) || m.name.eq_ignore_ascii_case(&project.title))
+			});
+
+			if let Some(other) = other &&
+				other.name != entry.name &&
+				!conflicts.iter().any(|(a, b)| {
+					a == &other.name && b == &entry.name || a == &entry.name && b == &other.name
+				}) {
+				conflicts.push((entry.name.clone(), other.name.clone()));
+			}
+		}
+	}
+
+	conflicts
+}
+
+pub fn load_lockfile(project_dir: &Path) -> anyhow::Result<Option<Lockfile>> {
+	let lock = project_dir.join("modpack.lock");
+
+	if lock.exists() {
+		let contents = fs::read_to_string(lock)?;
+		Ok(Some(ron::from_str(&contents)?))
 	} else {
-		Ok((None, manual_mods))
+		Ok(None)
+	}
+}
+
+pub fn update_lockfile(project_dir: &Path, lockfile: &Lockfile) -> anyhow::Result<()> {
+	let ron = ron::ser::to_string_pretty(lockfile, ron::ser::PrettyConfig::default())?;
+
+	Ok(fs::write(project_dir.join("modpack.lock"), ron)?)
+}
+
+/// Brings modpack.lock in line with modpack.ron. Mods that aren't locked yet are resolved and removed mods are
+/// dropped, but anything already locked stays exactly as it is. If the loader or minecraft version changed since the
+/// lockfile was written, everything is resolved again
+pub fn lock(project_dir: &Path) -> anyhow::Result<Lockfile> {
+	let existing = load_lockfile(project_dir)?;
+
+	resolve_lockfile(project_dir, existing)
+}
+
+/// Resolves `mods` to the newest files for the pack's loader and minecraft version, or every mod (and the loader) if
+/// `mods` is empty
+pub fn update(project_dir: &Path, mods: &[String]) -> anyhow::Result<Lockfile> {
+	let modpack = load_modpack(project_dir)?;
+	for name in mods {
+		if !modpack.mods.iter().any(|m| &m.name == name) {
+			return Err(anyhow!("There is no mod called {} in modpack.ron", name));
+		}
+	}
+
+	let existing = if mods.is_empty() {
+		None
+	} else {
+		load_lockfile(project_dir)?.map(|mut lockfile| {
+			lockfile.mods.retain(|m| !mods.contains(&m.name));
+			lockfile
+		})
+	};
+
+	resolve_lockfile(project_dir, existing)
+}
+
+fn resolve_lockfile(project_dir: &Path, existing: Option<Lockfile>) -> anyhow::Result<Lockfile> {
+	let config = load_config(project_dir)?;
+	let modpack = load_modpack(project_dir)?;
+
+	let loader = modpack.loader.ok_or(anyhow!(
+		"No loader specified in modpack.ron, run `modpackr check` first"
+	))?;
+	let minecraft_version = modpack.minecraft_version.ok_or(anyhow!(
+		"No minecraft version specified in modpack.ron, run `modpackr check` first"
+	))?;
+
+	let existing = existing.filter(|lockfile| {
+		lockfile.loader == loader && lockfile.minecraft_version == minecraft_version
+	});
+	let loader_version = match existing {
+		Some(ref lockfile) => lockfile.loader_version.clone(),
+		None => get_loader_version(&config.endpoints, loader.clone(), &minecraft_version)?,
+	};
+
+	let mut providers = Providers::new(&config.endpoints);
+	let mut mods = Vec::new();
+	for entry in modpack.mods.iter().filter(|m| !m.is_manual()) {
+		let filter = entry.file_filter(config.channel);
+		let locked = existing
+			.as_ref()
+			.and_then(|lockfile| lockfile.get(&entry.name))
+			.filter(|locked| locked.pin == filter.pin && locked.channel == filter.channel);
+
+		let mut files = Vec::new();
+		let mut errors = Vec::new();
+		for project in entry.providers.iter() {
+			if let Some(file) = locked.and_then(|m| m.file(&project.provider)) &&
+				file.project_id == project.id
+			{
+				let mut file = file.clone();
+				// Lockfiles from before sides were tracked don't have them
+				if file.sides.is_none() {
+					file.sides = providers.get(&project.provider)?.sides(project, &file)?;
+				}
+				files.push(file);
+				continue;
+			}
+
+			let file = providers
+				.select_file(
+					project,
+					&loader,
+					&minecraft_version,
+					&config.bridges,
+					&filter,
+				)
+				.and_then(|mut file| {
+					file.sides = providers.get(&project.provider)?.sides(project, &file)?;
+					Ok(file)
+				});
+			match file {
+				Ok(file) => files.push(file),
+				Err(e) => errors.push((project.provider.clone(), e)),
+			}
+		}
+
+		// A mod only has to be on one provider for the pack to work, exports for the others will list it as missing
+		if files.is_empty() && !errors.is_empty() {
+			let (_, e) = errors.remove(0);
+			return Err(anyhow!("Failed to lock {}: {}", entry.name, e));
+		}
+		for (provider, e) in errors {
+			println!(
+				"[WARN] No {} file locked for {}: {}",
+				provider, entry.name, e
+			);
+		}
+
+		mods.push(LockedMod {
+			name: entry.name.clone(),
+			files,
+			pin: filter.pin,
+			channel: filter.channel,
+		});
 	}
+
+	let lockfile = Lockfile {
+		loader,
+		minecraft_version,
+		loader_version,
+		mods,
+	};
+
+	update_lockfile(project_dir, &lockfile)?;
+
+	Ok(lockfile)
 }
 
+/// Exports the pack in one of the formats, or the one from config.toml if no flag is set. With a `side`, mods that
+/// don't run there are left out
 pub fn export(
 	project_dir: &Path,
 	curseforge: bool,
 	modrinth: bool,
 	neither: bool,
+	server: bool,
+	side: Option<Side>,
 ) -> anyhow::Result<()> {
-	let sum = curseforge as u8 + modrinth as u8 + neither as u8;
-	let modrinth = if sum == 0 { true } else { modrinth };
-	if sum != 1 {
+	let sum = curseforge as u8 + modrinth as u8 + neither as u8 + server as u8;
+	if sum > 1 {
 		return Err(anyhow!(
-			"You must specify either zero or one of -c, -m (the default if you specify none), or -n"
+			"You must specify either zero (to use the format in config.toml) or one of -c, -m, -n or -S"
 		));
 	}
 
-	let modpack = load_modpack(project_dir)?;
+	let config = load_config(project_dir)?;
+	let format = if curseforge {
+		ExportFormat::Curseforge
+	} else if modrinth {
+		ExportFormat::Modrinth
+	} else if neither {
+		ExportFormat::Modpackr
+	} else if server {
+		ExportFormat::Server
+	} else {
+		config.export.clone()
+	};
 
-	let modlist = modpack.mods.clone();
-	let loader = modpack.loader.clone();
-	let minecraft_version = modpack.minecraft_version.clone();
-	let name = modpack.name;
-	let author = modpack.author;
-	let version = modpack.version;
+	if format == ExportFormat::Modpackr && side.is_some() {
+		return Err(anyhow!(
+			"The modpackr format is the whole project, it can't be exported for one side"
+		));
+	}
+	if format == ExportFormat::Server && side == Some(Side::Client) {
+		return Err(anyhow!("A server pack can't be exported for the client"));
+	}
 
-	let mut both_provider_mods = Vec::new();
-	let mut modrinth_mods = Vec::new();
-	let mut curseforge_mods = Vec::new();
-	let mut manual_mods = Vec::new();
+	let modpack = load_modpack(project_dir)?;
 
-	for m in modlist.iter() {
-		match (&m.modrinth, &m.curseforge) {
-			(Some(_), Some(_)) => {
-				both_provider_mods.push(m);
-			},
-			(Some(_), None) => {
-				modrinth_mods.push(m);
-			},
-			(None, Some(_)) => {
-				curseforge_mods.push(m);
-			},
-			_ => {
-				manual_mods.push(m);
-			},
-		}
-	}
+	let manual_mods = modpack
+		.mods
+		.iter()
+		.filter(|m| {
+			m.is_manual() && !m.is_bundled() && side.is_none_or(|side| m.sides(None).supports(side))
+		})
+		.collect::<Vec<_>>();
 
 	if !manual_mods.is_empty() {
 		println!(
@@ -303,90 +961,828 @@ pub fn export(
 		);
 	}
 	for m in manual_mods.iter() {
-		println!("\t{}", m.name);
-	}
-
-	if curseforge {
-		if modrinth_mods.len() != 0 {
+		if m.jar.is_some() {
 			println!(
-				"[WARN] There are some mods that are only available on modrinth and must be installed manually: "
+				"\t{} (its jar is only shipped once its license is confirmed to allow it)",
+				m.name
 			);
-			for m in modrinth_mods {
+		} else {
+			println!("\t{}", m.name);
+		}
+	}
+
+	match format {
+		ExportFormat::Curseforge => {
+			export_curseforge(project_dir, &config, modpack, &lock(project_dir)?, side)
+		},
+		ExportFormat::Modrinth => {
+			export_modrinth(project_dir, &config, modpack, &lock(project_dir)?, side)
+		},
+		ExportFormat::Modpackr => export_modpackr(project_dir, &config, modpack),
+		ExportFormat::Server => export_server(project_dir, &config, modpack, &lock(project_dir)?),
+	}
+}
+
+fn export_curseforge(
+	project_dir: &Path,
+	config: &Config,
+	modpack: Modpack,
+	lockfile: &Lockfile,
+	side: Option<Side>,
+) -> anyhow::Result<()> {
+	let mut providers = Providers::new(&config.endpoints);
+	warn_missing_provider(
+		&modpack,
+		lockfile,
+		curseforge::PROVIDER,
+		side,
+		&mut providers,
+	);
+
+	let mod_loader = CurseforgeModLoaderEntry {
+		id: lockfile.loader_version.clone(),
+		primary: true,
+	};
+
+	let minecraft_manifest = CurseforgeMinecraftManifest {
+		version: lockfile.minecraft_version.clone(),
+		mod_loaders: vec![mod_loader],
+	};
+
+	let jars = manual_jars(project_dir, &modpack, side)?;
+
+	let mods = locked_files(&modpack, lockfile, curseforge::PROVIDER, side)
+		.into_iter()
+		.map(|(_, file)| {
+			Ok(CurseforgeManifestFile {
+				project_id: file.project_id.parse()?,
+				file_id: file.file_id.parse()?,
+			})
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let manifest = CurseforgeManifest {
+		minecraft: minecraft_manifest,
+		manifest_type: "minecraftModpack".to_owned(),
+		manifest_version: 1,
+		name: modpack.name.clone(),
+		version: modpack.version.clone(),
+		author: modpack.author,
+		files: mods,
+		overrides: "overrides".to_owned(),
+	};
+
+	let contents = serde_json::ser::to_string_pretty(&manifest)?;
+
+	let mut zip = create_export_zip(
+		project_dir,
+		&format!(
+			"{}-{}{}-curseforge.zip",
+			modpack.name,
+			modpack.version,
+			side_suffix(side)
+		),
+	)?;
+
+	zip.start_file("manifest.json", export_file_options())?;
+	zip.write_all(contents.as_bytes())?;
+
+	// Curseforge only has the one overrides folder, so the side folder is merged into it. Launchers install these
+	// packs as clients unless it's a server export
+	let mut files = override_files(
+		project_dir,
+		&override_dirs(Some(side.unwrap_or(Side::Client))),
+		&config.overrides,
+	)?;
+	files.extend(jars);
+	add_overrides_to_zip(&mut zip, files, "overrides")?;
+
+	zip.finish()?;
+
+	Ok(())
+}
+
+fn export_modrinth(
+	project_dir: &Path,
+	config: &Config,
+	modpack: Modpack,
+	lockfile: &Lockfile,
+	side: Option<Side>,
+) -> anyhow::Result<()> {
+	let mut providers = Providers::new(&config.endpoints);
+	warn_missing_provider(&modpack, lockfile, modrinth::PROVIDER, side, &mut providers);
+
+	let loader = &lockfile.loader;
+
+	// The lockfile has the curseforge style `<loader>-<version>`, modrinth only wants the version
+	let loader_version = lockfile
+		.loader_version
+		.strip_prefix(&format!("{loader}-"))
+		.unwrap_or(&lockfile.loader_version)
+		.to_owned();
+
+	let files = locked_files(&modpack, lockfile, modrinth::PROVIDER, side)
+		.into_iter()
+		.map(|(sides, file)| {
+			let hash = |algorithm: &str| {
+				file.hashes.get(algorithm).cloned().ok_or(anyhow!(
+					"{} has no {} hash",
+					file.file_name,
+					algorithm
+				))
+			};
+
+			Ok(ModrinthIndexFile {
+				path: format!("mods/{}", file.file_name),
+				hashes: ModrinthHashes {
+					sha1: hash("sha1")?,
+					sha512: hash("sha512")?,
+				},
+				env: Some(sides.into()),
+				downloads: file.url.clone().into_iter().collect(),
+				file_size: file.size,
+			})
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	let index = ModrinthIndex {
+		format_version: 1,
+		game: "minecraft".to_owned(),
+		version_id: modpack.version.clone(),
+		name: modpack.name.clone(),
+		files,
+		dependencies: BTreeMap::from([
+			("minecraft".to_owned(), lockfile.minecraft_version.clone()),
+			(modrinth_dependency_key(loader).to_owned(), loader_version),
+		]),
+	};
+
+	let contents = serde_json::ser::to_string_pretty(&index)?;
+
+	let mut zip = create_export_zip(
+		project_dir,
+		&format!(
+			"{}-{}{}.mrpack",
+			modpack.name,
+			modpack.version,
+			side_suffix(side)
+		),
+	)?;
+
+	zip.start_file("modrinth.index.json", export_file_options())?;
+	zip.write_all(contents.as_bytes())?;
+
+	// Modrinth launchers apply client-overrides and server-overrides themselves
+	let mut jars = manual_jars(project_dir, &modpack, side)?;
+	for dir in override_dirs(side) {
+		let mut files = override_files(project_dir, &[dir], &config.overrides)?;
+		if dir == "overrides" {
+			files.append(&mut jars);
+		}
+		add_overrides_to_zip(&mut zip, files, dir)?;
+	}
+
+	zip.finish()?;
+
+	Ok(())
+}
+
+/// The locked files from `provider` for every mod in the pack that's on it and runs on `side`, with where each one
+/// runs
+fn locked_files<'a>(
+	modpack: &Modpack,
+	lockfile: &'a Lockfile,
+	provider: &str,
+	side: Option<Side>,
+) -> Vec<(Sides, &'a ResolvedFile)> {
+	modpack
+		.mods
+		.iter()
+		.filter(|m| m.provider(provider).is_some())
+		.filter_map(|m| {
+			let locked = lockfile.get(&m.name);
+			let sides = m.sides(locked);
+			if let Some(side) = side &&
+				!sides.supports(side)
+			{
+				return None;
+			}
+
+			let file = locked.and_then(|locked| locked.file(provider));
+			if file.is_none() {
 				println!(
-					"\t {} https://modrinth.com/mod/{}",
-					m.name,
-					m.modrinth.as_ref().unwrap().id
+					"[WARN] {} has no {} file in modpack.lock for this version and must be installed manually",
+					m.name, provider
 				);
 			}
+			file.map(|file| (sides, file))
+		})
+		.collect()
+}
+
+/// Exports for one side are named like `pack-1.0.0-server.mrpack`
+fn side_suffix(side: Option<Side>) -> String {
+	side.map(|side| format!("-{side}")).unwrap_or_default()
+}
+
+/// Warns about mods that aren't on `provider`, since they have to be installed manually with this export format
+fn warn_missing_provider(
+	modpack: &Modpack,
+	lockfile: &Lockfile,
+	provider: &str,
+	side: Option<Side>,
+	providers: &mut Providers,
+) {
+	let missing = modpack
+		.mods
+		.iter()
+		.filter(|m| !m.is_manual() && m.provider(provider).is_none())
+		.filter(|m| side.is_none_or(|side| m.sides(lockfile.get(&m.name)).supports(side)))
+		.collect::<Vec<_>>();
+
+	if !missing.is_empty() {
+		println!(
+			"[WARN] There are some mods that are not available on {} and must be installed manually: ",
+			provider
+		);
+		for m in missing {
+			let project = &m.providers[0];
+			let url = providers
+				.get(&project.provider)
+				.map(|p| p.project_url(project))
+				.unwrap_or_default();
+
+			println!("\t {} {}", m.name, url);
 		}
+	}
+}
 
-		let mod_loader = CurseforgeModLoaderEntry {
-			id: get_loader_version(
-				loader
-					.clone()
-					.ok_or(anyhow!("No loader specified in modpack.ron"))?,
-				minecraft_version
-					.clone()
-					.ok_or(anyhow!("No minecraft version specified in modpack.ron"))?,
-			)?,
-			primary: true,
-		};
+fn export_modpackr(project_dir: &Path, config: &Config, modpack: Modpack) -> anyhow::Result<()> {
+	let mut zip = create_export_zip(
+		project_dir,
+		&format!("{}-{}.modpackr", modpack.name, modpack.version),
+	)?;
 
-		let minecraft_manifest = CurseforgeMinecraftManifest {
-			version: minecraft_version
-				.clone()
-				.ok_or(anyhow!("modpack.ron did not contain a version"))?,
-			mod_loaders: vec![mod_loader],
-		};
-		let curseforge = CurseforgeClient::new(get_api_key()?);
+	for file in ["config.toml", "modpack.ron", "modpack.lock"] {
+		let path = project_dir.join(file);
+		// Projects that were never exported or locked don't have a lockfile yet
+		if path.is_file() {
+			zip.start_file(file, export_file_options())?;
+			zip.write_all(&fs::read(path)?)?;
+		}
+	}
+
+	for dir in ["mods", "jars"] {
+		let path = project_dir.join(dir);
+		if path.is_dir() {
+			add_dir_to_zip(&mut zip, &path, dir)?;
+		}
+	}
+
+	for dir in override_dirs(None) {
+		let files = override_files(project_dir, &[dir], &config.overrides)?;
+		add_overrides_to_zip(&mut zip, files, dir)?;
+	}
+
+	zip.finish()?;
+
+	Ok(())
+}
+
+/// Writes `export/<name>-<version>-server`, a folder with the server's mods, overrides, loader, eula.txt and start
+/// scripts, and zips it up next to it
+fn export_server(
+	project_dir: &Path,
+	config: &Config,
+	modpack: Modpack,
+	lockfile: &Lockfile,
+) -> anyhow::Result<()> {
+	let name = format!("{}-{}-server", modpack.name, modpack.version);
+	let server_dir = project_dir.join("export").join(&name);
+	if server_dir.exists() {
+		fs::remove_dir_all(&server_dir)?;
+	}
+	fs::create_dir_all(server_dir.join("mods"))?;
 
-		let mods = curseforge_mods
+	let mut files = Vec::new();
+	for m in modpack.mods.iter() {
+		let locked = lockfile.get(&m.name);
+		if !m.sides(locked).supports(Side::Server) || m.is_bundled() {
+			continue;
+		}
+
+		// Curseforge files can have third party downloads disabled, so use whichever file can be downloaded
+		match locked
 			.into_iter()
-			.chain(both_provider_mods)
-			.map(|m| {
-				curseforge.latest_stable(
-					m.curseforge.as_ref().unwrap().id,
-					loader
-						.clone()
-						.ok_or(anyhow!("No loader specified in modpack.ron"))?,
-				)
-			})
-			.collect::<anyhow::Result<Vec<_>>>()?;
-
-		let manifest = CurseforgeManifest {
-			minecraft: minecraft_manifest,
-			manifest_type: "minecraftModpack".to_owned(),
-			manifest_version: 1,
-			name: name.clone(),
-			version: version.clone(),
-			author,
-			files: mods,
-			overrides: "overrides".to_owned(),
+			.flat_map(|locked| locked.files.iter())
+			.find(|file| file.url.is_some())
+		{
+			Some(file) => files.push(file),
+			None => println!(
+				"[WARN] {} can't be downloaded and must be added to the server's mods folder manually",
+				m.name
+			),
+		}
+	}
+
+	let store = Store::open()?;
+	store.add_root(project_dir)?;
+	let downloader = Downloader::new(store, config.parallel_downloads);
+	for (file, path) in files.iter().zip(downloader.fetch_all(&files)?) {
+		fs::copy(path, server_dir.join("mods").join(&file.file_name))?;
+	}
+
+	let mut overrides = override_files(
+		project_dir,
+		&override_dirs(Some(Side::Server)),
+		&config.overrides,
+	)?;
+	overrides.extend(manual_jars(project_dir, &modpack, Some(Side::Server))?);
+	for (name, path) in overrides {
+		let target = server_dir.join(name);
+		fs::create_dir_all(target.parent().unwrap())?;
+		fs::copy(path, target)?;
+	}
+
+	let launcher = server_launcher(
+		&config.endpoints,
+		&lockfile.loader,
+		&lockfile.minecraft_version,
+		&lockfile.loader_version,
+	)?;
+	// Launchers and installers don't come with hashes, so they aren't kept in the store
+	fs::write(
+		server_dir.join(&launcher.file_name),
+		HttpClient::new().download(&launcher.url)?,
+	)?;
+
+	let java = VersionCatalogue::fetch(&config.endpoints.mojang())?
+		.java_version(&lockfile.minecraft_version)?;
+	fs::write(server_dir.join("eula.txt"), EULA)?;
+	fs::write(
+		server_dir.join("start.bat"),
+		launcher.start_bat(&lockfile.minecraft_version, java),
+	)?;
+	let start_sh = server_dir.join("start.sh");
+	fs::write(
+		&start_sh,
+		launcher.start_sh(&lockfile.minecraft_version, java),
+	)?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(&start_sh, fs::Permissions::from_mode(0o755))?;
+	}
+
+	let mut zip = create_export_zip(project_dir, &format!("{name}.zip"))?;
+	add_dir_to_zip(&mut zip, &server_dir, &name)?;
+	zip.finish()?;
+
+	Ok(())
+}
+
+/// The override folders that go into an export for `side`, in the order they're layered
+fn override_dirs(side: Option<Side>) -> Vec<&'static str> {
+	match side {
+		None => vec!["overrides", "client-overrides", "server-overrides"],
+		Some(Side::Client) => vec!["overrides", "client-overrides"],
+		Some(Side::Server) => vec!["overrides", "server-overrides"],
+	}
+}
+
+/// Every file in the project's `dirs` that `filter` lets into exports, by its path inside the override folder. A file
+/// in a later folder replaces the same one from an earlier folder
+fn override_files(
+	project_dir: &Path,
+	dirs: &[&str],
+	filter: &OverrideFilter,
+) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+	let mut files = BTreeMap::new();
+	for dir in dirs {
+		let path = project_dir.join(dir);
+		if path.is_dir() {
+			collect_override_files(&path, "", filter, &mut files)?;
+		}
+	}
+
+	Ok(files)
+}
+
+fn collect_override_files(
+	dir: &Path,
+	prefix: &str,
+	filter: &OverrideFilter,
+	files: &mut BTreeMap<String, PathBuf>,
+) -> anyhow::Result<()> {
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		let name = path
+			.file_name()
+			.and_then(|os_str| os_str.to_str())
+			.ok_or(anyhow!("Invalid file name {}", path.display()))?;
+		let name = if prefix.is_empty() {
+			name.to_owned()
+		} else {
+			format!("{prefix}/{name}")
 		};
 
-		let contents = serde_json::ser::to_string_pretty(&manifest)?;
+		if path.is_dir() {
+			collect_override_files(&path, &name, filter, files)?;
+		} else if filter.allows(&name) {
+			files.insert(name, path);
+		}
+	}
 
-		let output_path = project_dir.join("export");
-		fs::create_dir_all(&output_path)?;
+	Ok(())
+}
 
-		let file =
-			fs::File::create(output_path.join(format!("{}-{}-curseforge.zip", name, version)))?;
+/// The jars of bundled manual mods that run on `side`, by where they go in an override folder like
+/// `mods/optifine.jar`. A jar replaces an override file with the same path
+fn manual_jars(
+	project_dir: &Path,
+	modpack: &Modpack,
+	side: Option<Side>,
+) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+	let mut jars = BTreeMap::new();
 
-		let mut zip = ZipWriter::new(file);
+	for m in modpack.mods.iter() {
+		if let Some(ref jar) = m.jar &&
+			m.is_bundled() &&
+			side.is_none_or(|side| m.sides(None).supports(side))
+		{
+			let path = project_dir.join("jars").join(&jar.file_name);
+			if !path.is_file() {
+				return Err(anyhow!(
+					"The jar of {} is missing, it should be at {}",
+					m.name,
+					path.display()
+				));
+			}
+			if !hex::encode(Sha1::digest(fs::read(&path)?)).eq_ignore_ascii_case(&jar.sha1) {
+				return Err(anyhow!(
+					"The jar of {} at {} doesn't match its sha1 hash, add it again if it was meant to change",
+					m.name,
+					path.display()
+				));
+			}
 
-		let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default()
-			.compression_method(zip::CompressionMethod::Stored)
-			.unix_permissions(0o644);
+			let name = format!("mods/{}", jar.file_name);
+			if jars.insert(name, path).is_some() {
+				return Err(anyhow!(
+					"More than one manual mod comes with a jar called {}",
+					jar.file_name
+				));
+			}
+		}
+	}
 
-		zip.start_file("manifest.json", options)?;
-		zip.write_all(contents.as_bytes())?;
+	Ok(jars)
+}
 
-		zip.finish()?;
-	} else if modrinth {
-		return Err(anyhow!("Still working on this, try modpackr or curseforge"));
+/// Adds override files from [`override_files`] to the zip under `prefix`
+fn add_overrides_to_zip(
+	zip: &mut ZipWriter<fs::File>,
+	files: BTreeMap<String, PathBuf>,
+	prefix: &str,
+) -> anyhow::Result<()> {
+	for (name, path) in files {
+		zip.start_file(format!("{prefix}/{name}"), export_file_options())?;
+		zip.write_all(&fs::read(path)?)?;
+	}
+
+	Ok(())
+}
+
+/// Recursively adds the contents of `dir` to the zip under `prefix`
+fn add_dir_to_zip(zip: &mut ZipWriter<fs::File>, dir: &Path, prefix: &str) -> anyhow::Result<()> {
+	zip.add_directory(prefix, export_file_options())?;
+
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		let name = format!(
+			"{}/{}",
+			prefix,
+			path.file_name()
+				.and_then(|os_str| os_str.to_str())
+				.ok_or(anyhow!("Invalid file name {}", path.display()))?
+		);
+
+		if path.is_dir() {
+			add_dir_to_zip(zip, &path, &name)?;
+		} else {
+			// Scripts have to stay runnable after unzipping
+			let options = if name.ends_with(".sh") {
+				export_file_options().unix_permissions(0o755)
+			} else {
+				export_file_options()
+			};
+			zip.start_file(name, options)?;
+			zip.write_all(&fs::read(&path)?)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Imports a `.modpackr`, `.mrpack` or curseforge `.zip` archive into a new project at `path`, returning the names
+/// of any mods that couldn't be matched to a provider
+pub fn import_archive(archive: &Path, path: &Path) -> anyhow::Result<Vec<String>> {
+	match archive.extension().and_then(|ext| ext.to_str()) {
+		Some("mrpack") => import_mrpack(archive, path),
+		Some("modpackr") => import_project(archive, path).map(|_| Vec::new()),
+		Some("zip") => import_curseforge(archive, path),
+		_ => Err(anyhow!(
+			"Don't know how to import {}, expected a .modpackr, .mrpack or curseforge .zip file",
+			archive.display()
+		)),
+	}
+}
+
+/// Unpacks a `.modpackr` archive made by `export -n` into a new project at `path`
+pub fn import_project(archive: &Path, path: &Path) -> anyhow::Result<()> {
+	ensure_empty_project_dir(path)?;
+
+	let mut zip = ZipArchive::new(fs::File::open(archive)?)?;
+
+	for file in ["config.toml", "modpack.ron"] {
+		if zip.index_for_name(file).is_none() {
+			return Err(anyhow!(
+				"{} is not a modpackr archive, it is missing {}",
+				archive.display(),
+				file
+			));
+		}
+	}
+
+	fs::create_dir_all(path)?;
+	extract_zip(&mut zip, path, |name| Some(name.to_path_buf()))?;
+	fs::create_dir_all(path.join("mods"))?;
+
+	// Make sure what we unpacked is actually usable
+	load_config(path)?;
+	load_modpack(path)?;
+
+	Ok(())
+}
+
+/// Creates a new project at `path` from a Modrinth `.mrpack`, returning the names of files that couldn't be matched
+/// to a modrinth project and were added as manual mods instead
+pub fn import_mrpack(archive: &Path, path: &Path) -> anyhow::Result<Vec<String>> {
+	ensure_empty_project_dir(path)?;
+
+	let mut zip = ZipArchive::new(fs::File::open(archive)?)?;
+	let index: ModrinthIndex = serde_json::from_reader(
+		zip.by_name("modrinth.index.json")
+			.map_err(|_| anyhow!("{} is missing modrinth.index.json", archive.display()))?,
+	)?;
+
+	let (mod_files, other_files): (Vec<_>, Vec<_>) = index
+		.files
+		.iter()
+		.partition(|file| file.path.starts_with("mods/"));
+
+	let hashes = mod_files
+		.iter()
+		.map(|file| file.hashes.sha1.clone())
+		.collect::<Vec<_>>();
+	let modrinth = ModrinthClient::with_base_url(Endpoints::default().modrinth());
+	let versions = modrinth.versions_from_hashes(&hashes)?;
+
+	let project_ids = versions
+		.values()
+		.map(|version| version.project_id.clone())
+		.collect::<BTreeSet<_>>();
+	let projects = modrinth
+		.get_mods(&project_ids)?
+		.into_iter()
+		.map(|project| (project.id.clone(), project))
+		.collect::<BTreeMap<_, _>>();
+
+	let mut mods = Vec::new();
+	let mut manual_mods = Vec::new();
+	for file in mod_files {
+		let project = versions
+			.get(&file.hashes.sha1)
+			.and_then(|version| projects.get(&version.project_id));
+
+		if let Some(project) = project {
+			mods.push(Mod::new(&project.title, vec![project.clone().into()]));
+		} else {
+			let file_path = Path::new(&file.path);
+			let name = file_path
+				.file_stem()
+				.and_then(|os_str| os_str.to_str())
+				.unwrap_or(&file.path)
+				.to_owned();
+			let file_name = file_path
+				.file_name()
+				.and_then(|os_str| os_str.to_str())
+				.unwrap_or(&file.path)
+				.to_owned();
+
+			// Where the pack got the jar from is kept, but whether it may be shipped is still up to the user
+			manual_mods.push(name.clone());
+			mods.push(Mod {
+				jar: Some(ManualJar {
+					file_name,
+					sha1: file.hashes.sha1.clone(),
+					url: file.downloads.first().cloned(),
+					redistributable: false,
+				}),
+				..Mod::new(name, Vec::new())
+			});
+		}
+	}
+
+	let loader = index
+		.dependencies
+		.keys()
+		.find_map(|key| modrinth_loader_from_key(key));
+
+	create_project_at_path(path)?;
+
+	let mut modpack = load_modpack(path)?;
+	modpack.name = index.name;
+	modpack.version = index.version_id;
+	modpack.minecraft_version = index.dependencies.get("minecraft").cloned();
+	modpack.loader = loader;
+	modpack.mods = mods;
+	update_modpack(path, modpack)?;
+
+	// Resource packs, shader packs and the like are downloaded into the overrides, the side that doesn't use them is
+	// left out
+	let mut targets = Vec::new();
+	let mut files = Vec::new();
+	for file in other_files.iter() {
+		let relative = Path::new(&file.path);
+		if !relative
+			.components()
+			.all(|component| matches!(component, Component::Normal(_)))
+		{
+			return Err(anyhow!("Archive contains an unsafe path: {}", file.path));
+		}
+
+		let dir = match file.env {
+			Some(ref env) if env.server == "unsupported" => "client-overrides",
+			Some(ref env) if env.client == "unsupported" => "server-overrides",
+			_ => "overrides",
+		};
+		targets.push(path.join(dir).join(relative));
+		files.push(ResolvedFile {
+			provider: modrinth::PROVIDER.to_owned(),
+			file_name: file.path.clone(),
+			hashes: BTreeMap::from([
+				("sha1".to_owned(), file.hashes.sha1.clone()),
+				("sha512".to_owned(), file.hashes.sha512.clone()),
+			]),
+			url: file.downloads.first().cloned(),
+			size: file.file_size,
+			..Default::default()
+		});
+	}
+
+	if !files.is_empty() {
+		let downloader = Downloader::new(Store::open()?, load_config(path)?.parallel_downloads);
+		let files = files.iter().collect::<Vec<_>>();
+		for (target, stored) in targets.iter().zip(downloader.fetch_all(&files)?) {
+			fs::create_dir_all(target.parent().unwrap())?;
+			fs::copy(stored, target)?;
+		}
+	}
+
+	extract_zip(&mut zip, path, |name| {
+		["overrides", "client-overrides", "server-overrides"]
+			.iter()
+			.any(|dir| name.starts_with(dir))
+			.then(|| name.to_path_buf())
+	})?;
+
+	Ok(manual_mods)
+}
+
+/// Creates a new project at `path` from a curseforge modpack zip, returning the names of projects that couldn't be
+/// looked up and were added as manual mods instead
+pub fn import_curseforge(archive: &Path, path: &Path) -> anyhow::Result<Vec<String>> {
+	ensure_empty_project_dir(path)?;
+
+	let mut zip = ZipArchive::new(fs::File::open(archive)?)?;
+	let manifest: CurseforgeManifest = serde_json::from_reader(
+		zip.by_name("manifest.json")
+			.map_err(|_| anyhow!("{} is missing manifest.json", archive.display()))?,
+	)?;
+
+	let loader = manifest
+		.minecraft
+		.mod_loaders
+		.iter()
+		.find(|entry| entry.primary)
+		.or(manifest.minecraft.mod_loaders.first())
+		.map(|entry| parse_loader_version(&entry.id))
+		.transpose()?;
+
+	let curseforge =
+		CurseforgeClient::with_base_url(get_api_key()?, Endpoints::default().curseforge());
+
+	let mut mods = Vec::new();
+	let mut manual_mods = Vec::new();
+	for file in manifest.files.iter() {
+		match curseforge.get_mod(file.project_id) {
+			Ok(curseforge_mod) => mods.push(Mod::new(
+				curseforge_mod.name.clone(),
+				vec![curseforge_mod.into()],
+			)),
+			Err(e) => {
+				println!(
+					"[WARN] Failed to look up curseforge project {}: {e}",
+					file.project_id
+				);
+
+				let name = format!("curseforge-{}", file.project_id);
+				manual_mods.push(name.clone());
+				mods.push(Mod::new(name, Vec::new()));
+			},
+		}
+	}
+
+	create_project_at_path(path)?;
+
+	let mut modpack = load_modpack(path)?;
+	modpack.name = manifest.name;
+	modpack.version = manifest.version;
+	modpack.author = manifest.author;
+	modpack.minecraft_version = Some(manifest.minecraft.version);
+	modpack.loader = loader.map(|(loader, _)| loader);
+	modpack.mods = mods;
+	update_modpack(path, modpack)?;
+
+	// An empty prefix would match every entry, manifest.json included, so that means the usual folder
+	let overrides = match manifest.overrides.trim_matches('/') {
+		"" => Path::new("overrides"),
+		overrides => Path::new(overrides),
+	};
+	extract_zip(&mut zip, path, |name| {
+		name.strip_prefix(overrides)
+			.ok()
+			.map(|rest| Path::new("overrides").join(rest))
+	})?;
+
+	Ok(manual_mods)
+}
+
+fn ensure_empty_project_dir(path: &Path) -> anyhow::Result<()> {
+	if path.join("modpack.ron").exists() {
+		Err(anyhow!(
+			"{} already contains a modpack, choose an empty directory to import into",
+			path.display()
+		))
 	} else {
-		return Err(anyhow!("Still working on this, try modrinth or curseforge"));
+		Ok(())
+	}
+}
+
+/// Extracts the entries of the zip into `path`, `map` gives where each entry should go relative to `path` or `None`
+/// to skip it
+fn extract_zip(
+	zip: &mut ZipArchive<fs::File>,
+	path: &Path,
+	map: impl Fn(&Path) -> Option<PathBuf>,
+) -> anyhow::Result<()> {
+	for i in 0..zip.len() {
+		let mut file = zip.by_index(i)?;
+		let name = file
+			.enclosed_name()
+			.ok_or(anyhow!("Archive contains an unsafe path: {}", file.name()))?;
+
+		let Some(name) = map(&name) else {
+			continue;
+		};
+
+		let out_path = path.join(name);
+
+		if file.is_dir() {
+			fs::create_dir_all(&out_path)?;
+		} else {
+			if let Some(parent) = out_path.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			io::copy(&mut file, &mut fs::File::create(&out_path)?)?;
+		}
 	}
 
 	Ok(())
 }
+
+fn create_export_zip(project_dir: &Path, file_name: &str) -> anyhow::Result<ZipWriter<fs::File>> {
+	let output_path = project_dir.join("export");
+	fs::create_dir_all(&output_path)?;
+
+	let file = fs::File::create(output_path.join(file_name))?;
+
+	Ok(ZipWriter::new(file))
+}
+
+fn export_file_options() -> FileOptions<'static, ExtendedFileOptions> {
+	FileOptions::default()
+		.compression_method(zip::CompressionMethod::Stored)
+		.unix_permissions(0o644)
+}
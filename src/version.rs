@@ -0,0 +1,322 @@
+use {
+	serde::{Deserialize, Deserializer, Serialize, Serializer},
+	std::{cmp::Ordering, convert::Infallible, fmt::Display, str::FromStr},
+};
+
+/// A minecraft version like `1.20.1`, `1.21-pre1`, `1.21-rc1`, `24w14a` or `26.1-snapshot-1`, ordered the way they
+/// were released rather than alphabetically, with weekly snapshots just before the release they lead up to. Strings it
+/// doesn't recognise (april fools versions, old alphas) are kept as they are and sort before everything else
+#[derive(Debug, Clone)]
+pub struct MinecraftVersion {
+	raw: String,
+	key: Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key {
+	Unknown,
+	Snapshot {
+		year: u32,
+		week: u32,
+		tag: String,
+	},
+	// `snapshot` counts the snapshots of versions numbered by year, like `26.1-snapshot-2`
+	Numbered {
+		parts: Vec<u32>,
+		stage: Stage,
+		snapshot: u32,
+	},
+}
+
+/// How far along a numbered version is, in release order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+	// Curseforge lists snapshots of an upcoming version as `1.21-Snapshot`
+	Snapshot,
+	PreRelease(u32),
+	ReleaseCandidate(u32),
+	Release,
+}
+
+impl MinecraftVersion {
+	pub fn new<T: Into<String>>(version: T) -> Self {
+		let raw = version.into();
+		let key = parse_key(&raw);
+
+		Self { raw, key }
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.raw
+	}
+
+	/// Full releases only, not snapshots, pre-releases or release candidates
+	pub fn is_release(&self) -> bool {
+		matches!(
+			self.key,
+			Key::Numbered {
+				stage: Stage::Release,
+				..
+			}
+		)
+	}
+
+	/// Whether this is a version string minecraft has used at all
+	pub fn is_known(&self) -> bool {
+		self.key != Key::Unknown
+	}
+
+	/// The Java version the server and client need, going by the name alone. Unrecognised versions are assumed to be old
+	/// enough for Java 8, [`crate::mojang::VersionCatalogue::java_version`] knows better for published versions
+	pub fn java_version(&self) -> u32 {
+		match self.key {
+			Key::Numbered { ref parts, .. } => match parts[..] {
+				[0, ..] => 8,
+				[1, minor, ..] if minor < 17 => 8,
+				[1, 17, ..] => 16,
+				[1, 18 | 19, ..] | [1, 20, 0..=4] | [1, 20] => 17,
+				[1, ..] => 21,
+				// Numbered by year since 26.1, which moved to Java 25
+				_ => 25,
+			},
+			// 21w19a moved to Java 16, 21w37a to 17 and 24w14a to 21
+			Key::Snapshot { year, week, .. } => match (year, week) {
+				(24, 14..) | (25.., _) => 21,
+				(21, 37..) | (22..=24, _) => 17,
+				(21, 19..) => 16,
+				_ => 8,
+			},
+			Key::Unknown => 8,
+		}
+	}
+
+	/// The stage of a numbered version, weekly snapshots count as [`Stage::Snapshot`]
+	pub fn stage(&self) -> Option<Stage> {
+		match self.key {
+			Key::Numbered { stage, .. } => Some(stage),
+			Key::Snapshot { .. } => Some(Stage::Snapshot),
+			Key::Unknown => None,
+		}
+	}
+}
+
+/// The last week of snapshots for each release, in order. Weekly snapshots don't say which release they lead up to,
+/// so they're placed with the first release whose snapshots end on or after their week
+const SNAPSHOT_TARGETS: &[(u32, u32, &[u32])] = &[
+	(11, 50, &[1, 1]),
+	(12, 8, &[1, 2]),
+	(12, 30, &[1, 3]),
+	(12, 42, &[1, 4]),
+	(12, 50, &[1, 4, 6]),
+	(13, 10, &[1, 5]),
+	(13, 11, &[1, 5, 1]),
+	(13, 26, &[1, 6]),
+	(13, 43, &[1, 7]),
+	(13, 49, &[1, 7, 4]),
+	(14, 34, &[1, 8]),
+	(16, 7, &[1, 9]),
+	(16, 15, &[1, 9, 3]),
+	(16, 21, &[1, 10]),
+	(16, 44, &[1, 11]),
+	(16, 50, &[1, 11, 1]),
+	(17, 18, &[1, 12]),
+	(17, 31, &[1, 12, 1]),
+	(18, 22, &[1, 13]),
+	(18, 33, &[1, 13, 1]),
+	(19, 14, &[1, 14]),
+	(19, 46, &[1, 15]),
+	(20, 22, &[1, 16]),
+	(20, 30, &[1, 16, 2]),
+	(21, 20, &[1, 17]),
+	(21, 44, &[1, 18]),
+	(22, 7, &[1, 18, 2]),
+	(22, 19, &[1, 19]),
+	(22, 24, &[1, 19, 1]),
+	(22, 46, &[1, 19, 3]),
+	(23, 7, &[1, 19, 4]),
+	(23, 18, &[1, 20]),
+	(23, 35, &[1, 20, 2]),
+	(23, 46, &[1, 20, 3]),
+	(24, 14, &[1, 20, 5]),
+	(24, 21, &[1, 21]),
+	(24, 40, &[1, 21, 2]),
+	(24, 46, &[1, 21, 4]),
+	(25, 10, &[1, 21, 5]),
+	(25, 21, &[1, 21, 6]),
+	(25, 37, &[1, 21, 9]),
+	(25, 46, &[1, 21, 11]),
+];
+
+impl Key {
+	/// What versions are ordered by: whether they're known, the release they belong to, how far along they are and then
+	/// the week of weekly snapshots. Snapshots newer than [`SNAPSHOT_TARGETS`] lead up to a release it doesn't know
+	/// about yet, so they sort after every numbered version
+	fn order(&self) -> (bool, bool, &[u32], Stage, u32, u32, &str) {
+		match self {
+			Self::Unknown => (false, false, &[], Stage::Snapshot, 0, 0, ""),
+			Self::Numbered {
+				parts,
+				stage,
+				snapshot,
+			} => (true, false, parts, *stage, 0, *snapshot, ""),
+			Self::Snapshot { year, week, tag } => {
+				match SNAPSHOT_TARGETS
+					.iter()
+					.find(|(last_year, last_week, _)| (year, week) <= (last_year, last_week))
+				{
+					Some((_, _, target)) => {
+						(true, false, target, Stage::Snapshot, *year, *week, tag)
+					},
+					None => (true, true, &[], Stage::Snapshot, *year, *week, tag),
+				}
+			},
+		}
+	}
+}
+
+impl PartialOrd for Key {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Key {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.order().cmp(&other.order())
+	}
+}
+
+fn parse_key(version: &str) -> Key {
+	parse_numbered(version)
+		.or_else(|| parse_snapshot(version))
+		.unwrap_or(Key::Unknown)
+}
+
+/// `1.20.1`, `1.21-pre1`, `1.21-rc1`, `1.21-Snapshot`, the older `1.14 Pre-Release 1` and the year numbered
+/// `26.1-snapshot-1`, `26.1-pre-1` and `26.1-rc-1`
+fn parse_numbered(version: &str) -> Option<Key> {
+	let (numbers, suffix) = match version.find(['-', ' ']) {
+		Some(index) => (&version[..index], Some(&version[index + 1..])),
+		None => (version, None),
+	};
+
+	let parts = numbers
+		.split('.')
+		.map(|part| part.parse().ok())
+		.collect::<Option<Vec<u32>>>()?;
+	if parts.len() < 2 {
+		return None;
+	}
+
+	let mut snapshot = 0;
+	let stage = match suffix.map(str::to_lowercase) {
+		None => Stage::Release,
+		Some(suffix) => {
+			let number = |prefix: &str| {
+				suffix
+					.strip_prefix(prefix)?
+					.trim_start_matches('-')
+					.trim()
+					.parse()
+					.ok()
+			};
+
+			if suffix == "snapshot" {
+				Stage::Snapshot
+			} else if let Some(n) = number("snapshot") {
+				snapshot = n;
+				Stage::Snapshot
+			} else if let Some(n) = number("pre-release").or_else(|| number("pre")) {
+				Stage::PreRelease(n)
+			} else if let Some(n) = number("release candidate").or_else(|| number("rc")) {
+				Stage::ReleaseCandidate(n)
+			} else {
+				return None;
+			}
+		},
+	};
+
+	Some(Key::Numbered {
+		parts,
+		stage,
+		snapshot,
+	})
+}
+
+/// `24w14a`
+fn parse_snapshot(version: &str) -> Option<Key> {
+	let (year, rest) = version.split_once('w')?;
+	let week_len = rest.chars().take_while(char::is_ascii_digit).count();
+	let (week, tag) = rest.split_at(week_len);
+
+	if year.len() != 2 || week_len != 2 || !tag.chars().all(|c| c.is_ascii_lowercase()) {
+		return None;
+	}
+
+	Some(Key::Snapshot {
+		year: year.parse().ok()?,
+		week: week.parse().ok()?,
+		tag: tag.to_owned(),
+	})
+}
+
+impl PartialEq for MinecraftVersion {
+	fn eq(&self, other: &Self) -> bool {
+		self.raw == other.raw
+	}
+}
+
+impl Eq for MinecraftVersion {}
+
+impl PartialOrd for MinecraftVersion {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for MinecraftVersion {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.key
+			.cmp(&other.key)
+			.then_with(|| self.raw.cmp(&other.raw))
+	}
+}
+
+impl Display for MinecraftVersion {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.raw)
+	}
+}
+
+impl FromStr for MinecraftVersion {
+	type Err = Infallible;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(Self::new(s))
+	}
+}
+
+impl From<&str> for MinecraftVersion {
+	fn from(value: &str) -> Self {
+		Self::new(value)
+	}
+}
+
+impl From<String> for MinecraftVersion {
+	fn from(value: String) -> Self {
+		Self::new(value)
+	}
+}
+
+// Stored as the plain string, so mods/<name>.ron files from before this type existed still load
+impl Serialize for MinecraftVersion {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.raw)
+	}
+}
+
+impl<'de> Deserialize<'de> for MinecraftVersion {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(Self::new(String::deserialize(deserializer)?))
+	}
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Bridge, ModLoader, ModVersions,
+		provider::{FileFilter, NoFile, Providers},
+		util::{check, load_config, lock, update_config},
+		version::MinecraftVersion,
+	},
+	std::{collections::BTreeSet, path::Path},
+};
+
+fn versions(list: &[&str]) -> BTreeSet<MinecraftVersion> {
+	list.iter().map(|v| MinecraftVersion::new(*v)).collect()
+}
+
+fn force_loader(path: &Path, loader: ModLoader, bridges: Vec<Bridge>) {
+	let mut config = load_config(path).unwrap();
+	config.loader = Some(loader);
+	config.bridges = bridges;
+	update_config(path, &config).unwrap();
+}
+
+#[test]
+fn default_bridges_add_fabric_to_quilt_and_forge_1_20_1_to_neoforge() {
+	let mod_versions = ModVersions {
+		fabric: versions(&["1.20.1", "1.20.4"]),
+		forge: versions(&["1.19.2", "1.20.1"]),
+		..Default::default()
+	};
+
+	let bridged = mod_versions.bridged(&Bridge::defaults());
+
+	assert_eq!(bridged.quilt, versions(&["1.20.1", "1.20.4"]));
+	assert_eq!(bridged.neo_forge, versions(&["1.20.1"]));
+	assert_eq!(bridged.fabric, mod_versions.fabric);
+	assert_eq!(mod_versions.bridged(&[]), mod_versions);
+}
+
+#[test]
+fn check_uses_fabric_mods_on_quilt() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			modrinth_mod("Fabric API", "P7dR8mSH", "fabric-api"),
+		],
+	);
+	let path = project_path(&dir);
+	force_loader(&path, ModLoader::Quilt, Bridge::defaults());
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(result.target, Some((ModLoader::Quilt, "1.20.4".to_owned())));
+	assert_eq!(
+		result.bridged,
+		vec![
+			("Sodium".to_owned(), ModLoader::Fabric),
+			("Fabric API".to_owned(), ModLoader::Fabric),
+		]
+	);
+}
+
+#[test]
+fn check_without_bridges_keeps_loaders_apart() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![modrinth_mod("Fabric API", "P7dR8mSH", "fabric-api")],
+	);
+	let path = project_path(&dir);
+	force_loader(&path, ModLoader::Quilt, Vec::new());
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(result.target, None);
+	assert!(result.bridged.is_empty());
+}
+
+#[test]
+fn check_uses_forge_mods_on_neoforge_1_20_1() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+	force_loader(&path, ModLoader::Neoforge, Bridge::defaults());
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Neoforge, "1.20.1".to_owned()))
+	);
+	assert_eq!(
+		result.bridged,
+		vec![("Just Enough Items".to_owned(), ModLoader::Forge)]
+	);
+}
+
+#[test]
+fn lockfile_records_bridged_files() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			modrinth_mod("Fabric API", "P7dR8mSH", "fabric-api"),
+		],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Quilt, "1.20.1");
+
+	let lockfile = lock(&path).unwrap();
+
+	// Sodium 1.20.1 lists quilt itself, fabric api doesn't
+	let sodium = lockfile.get("Sodium").unwrap().file("modrinth").unwrap();
+	assert_eq!(sodium.bridged_from, None);
+	let fabric_api = lockfile
+		.get("Fabric API")
+		.unwrap()
+		.file("modrinth")
+		.unwrap();
+	assert_eq!(fabric_api.bridged_from, Some(ModLoader::Fabric));
+	assert_eq!(fabric_api.file_name, "fabric-api-0.92.0+1.20.1.jar");
+}
+
+#[test]
+fn only_a_missing_file_falls_through_to_the_bridges() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let mut providers = Providers::new(&server.endpoints());
+	let select = |providers: &mut Providers, id: &str| {
+		providers.select_file(
+			&modrinth_mod("Sodium", id, "sodium").providers[0],
+			&ModLoader::Quilt,
+			"1.12.2",
+			&Bridge::defaults(),
+			&FileFilter::default(),
+		)
+	};
+
+	// Sodium is there, just not for 1.12.2 on either loader
+	assert!(
+		select(&mut providers, "AANobbMI")
+			.unwrap_err()
+			.is::<NoFile>()
+	);
+	// A project that can't be fetched is an error of its own, not a missing file
+	assert!(
+		!select(&mut providers, "missing")
+			.unwrap_err()
+			.is::<NoFile>()
+	);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader,
+		provider::{Channel, FileFilter},
+		util::{check, edit_mod, load_config, lock, update_config},
+	},
+	std::path::Path,
+};
+
+fn set_channel(path: &Path, channel: Channel) {
+	let mut config = load_config(path).unwrap();
+	config.channel = channel;
+	update_config(path, &config).unwrap();
+}
+
+fn iris() -> modpackr::Mod {
+	modrinth_mod("Iris Shaders", "YL57xq9U", "iris")
+}
+
+#[test]
+fn channels_allow_everything_more_stable_than_them() {
+	let beta = FileFilter {
+		channel: Channel::Beta,
+		pin: None,
+	};
+
+	assert!(beta.allows("modrinth", "a", &[], &[], Channel::Release));
+	assert!(beta.allows("modrinth", "a", &[], &[], Channel::Beta));
+	assert!(!beta.allows("modrinth", "a", &[], &[], Channel::Alpha));
+	assert!(!FileFilter::default().allows("modrinth", "a", &[], &[], Channel::Beta));
+}
+
+#[test]
+fn check_only_counts_releases_by_default() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![iris(), modrinth_mod("Sodium", "AANobbMI", "sodium")],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	// Iris only has a beta for 1.20.4
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+
+	set_channel(&path, Channel::Beta);
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+}
+
+#[test]
+fn a_mod_can_override_the_pack_channel() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![iris()]);
+	let path = project_path(&dir);
+	edit_mod(&path, "Iris Shaders", |m| m.channel = Some(Channel::Beta)).unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+}
+
+#[test]
+fn lock_follows_the_channel_and_relocks_when_it_changes() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			curseforge_mod("Just Enough Items", 238222, "jei"),
+		],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	let release = lock(&path).unwrap();
+	assert_eq!(
+		release.get("Sodium").unwrap().files[0].file_name,
+		"sodium-fabric-mc1.20.1-0.5.3.jar"
+	);
+
+	set_channel(&path, Channel::Alpha);
+	let alpha = lock(&path).unwrap();
+
+	assert_eq!(
+		alpha.get("Sodium").unwrap().files[0].file_name,
+		"sodium-fabric-mc1.20.1-0.5.4-alpha.jar"
+	);
+	// JEI has nothing newer for 1.20.1, so it relocks to the same file
+	assert_eq!(
+		alpha.get("Just Enough Items").unwrap().files,
+		release.get("Just Enough Items").unwrap().files
+	);
+	assert_eq!(alpha.get("Sodium").unwrap().channel, Channel::Alpha);
+}
+
+#[test]
+fn curseforge_betas_are_only_used_when_allowed() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.4");
+
+	let error = lock(&path).unwrap_err().to_string();
+	assert!(error.contains("on the release channel"), "{error}");
+
+	set_channel(&path, Channel::Beta);
+	let lockfile = lock(&path).unwrap();
+
+	assert_eq!(
+		lockfile.get("Just Enough Items").unwrap().files[0].file_id,
+		"5101366"
+	);
+}
@@ -0,0 +1,328 @@
+#![allow(dead_code)]
+
+use {
+	modpackr::{
+		Endpoints, Mod, ModLoader,
+		http::set_offline,
+		provider::ProviderRef,
+		util::{create_project_at_path, load_config, load_modpack, update_config, update_modpack},
+	},
+	std::{
+		fs::File,
+		hash::{DefaultHasher, Hash, Hasher},
+		io::{BufRead, BufReader, Read, Write},
+		net::{TcpListener, TcpStream},
+		path::{Path, PathBuf},
+		sync::{Arc, Mutex, MutexGuard, Once},
+		thread,
+	},
+	tempfile::TempDir,
+	zip::ZipArchive,
+};
+
+/// Serves the recorded API responses in tests/fixtures over HTTP. `GET /modrinth/project/x` is answered with
+/// `fixtures/modrinth/project/x` or, if that doesn't exist, `fixtures/modrinth/project/x.json`. Query strings are
+/// ignored, except that later pages of a list (`?index=N` with N other than 0) come from `x@N` instead. Responses
+/// carry an ETag, and a matching If-None-Match is answered with 304 Not Modified. `Range: bytes=N-` is answered with
+/// 206 Partial Content and everything from byte N
+pub struct FixtureServer {
+	pub base_url: String,
+	// Path and response status of every request
+	requests: Arc<Mutex<Vec<(String, u16)>>>,
+}
+
+impl FixtureServer {
+	pub fn start() -> Self {
+		let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind fixture server");
+		let base_url = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(Mutex::new(Vec::new()));
+
+		let log = requests.clone();
+		thread::spawn(move || {
+			for stream in listener.incoming().flatten() {
+				let log = log.clone();
+				thread::spawn(move || handle(stream, log));
+			}
+		});
+
+		Self { base_url, requests }
+	}
+
+	pub fn endpoints(&self) -> Endpoints {
+		Endpoints {
+			modrinth: Some(format!("{}/modrinth", self.base_url)),
+			curseforge: Some(format!("{}/curseforge", self.base_url)),
+			fabric: Some(format!("{}/fabric", self.base_url)),
+			quilt: Some(format!("{}/quilt", self.base_url)),
+			forge_maven: Some(format!("{}/forge", self.base_url)),
+			neoforge_maven: Some(format!("{}/neoforge", self.base_url)),
+			mojang: Some(format!("{}/mojang", self.base_url)),
+		}
+	}
+
+	/// Paths of every request served so far, in order
+	pub fn requests(&self) -> Vec<String> {
+		self.requests
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|(path, _)| path.clone())
+			.collect()
+	}
+
+	/// Response statuses of every request served so far, in order
+	pub fn statuses(&self) -> Vec<u16> {
+		self.requests
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|(_, status)| *status)
+			.collect()
+	}
+}
+
+fn handle(stream: TcpStream, log: Arc<Mutex<Vec<(String, u16)>>>) {
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line).unwrap();
+	let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+	let path = target.split('?').next().unwrap().to_owned();
+	let page = target
+		.split_once('?')
+		.and_then(|(_, query)| {
+			query
+				.split('&')
+				.find_map(|param| param.strip_prefix("index="))
+		})
+		.filter(|index| *index != "0");
+
+	let mut content_length = 0;
+	let mut if_none_match = None;
+	let mut range_start = None;
+	loop {
+		let mut header = String::new();
+		reader.read_line(&mut header).unwrap();
+		if header.trim().is_empty() {
+			break;
+		}
+		if let Some((name, value)) = header.split_once(':') &&
+			name.eq_ignore_ascii_case("content-length")
+		{
+			content_length = value.trim().parse().unwrap_or(0);
+		} else if let Some((name, value)) = header.split_once(':') &&
+			name.eq_ignore_ascii_case("if-none-match")
+		{
+			if_none_match = Some(value.trim().to_owned());
+		} else if let Some((name, value)) = header.split_once(':') &&
+			name.eq_ignore_ascii_case("range")
+		{
+			range_start = value
+				.trim()
+				.strip_prefix("bytes=")
+				.and_then(|range| range.strip_suffix('-'))
+				.and_then(|start| start.parse::<usize>().ok());
+		}
+	}
+	let mut body = vec![0; content_length];
+	reader.read_exact(&mut body).unwrap();
+
+	let file = match page {
+		Some(index) => fixtures_dir().join(format!("{}@{index}", path.trim_start_matches('/'))),
+		None => fixtures_dir().join(path.trim_start_matches('/')),
+	};
+	let file = if file.is_file() {
+		Some(file)
+	} else {
+		let json = PathBuf::from(format!("{}.json", file.display()));
+		json.is_file().then_some(json)
+	};
+
+	let mut stream = stream;
+	let (status, response) = match file.map(std::fs::read) {
+		Some(Ok(contents)) => {
+			let mut hasher = DefaultHasher::new();
+			contents.hash(&mut hasher);
+			let etag = format!("\"{:x}\"", hasher.finish());
+
+			if if_none_match.as_ref() == Some(&etag) {
+				(
+					304,
+					format!(
+						"HTTP/1.1 304 Not Modified\r\nETag: {etag}\r\nConnection: close\r\n\r\n"
+					)
+					.into_bytes(),
+				)
+			} else if let Some(start) = range_start &&
+				start < contents.len()
+			{
+				let mut response = format!(
+					"HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+					contents.len() - start,
+					start,
+					contents.len() - 1,
+					contents.len()
+				)
+				.into_bytes();
+				response.extend(&contents[start..]);
+				(206, response)
+			} else {
+				let mut response = format!(
+					"HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: {etag}\r\nConnection: close\r\n\r\n",
+					contents.len()
+				)
+				.into_bytes();
+				response.extend(contents);
+				(200, response)
+			}
+		},
+		_ => (
+			404,
+			b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+		),
+	};
+
+	log.lock().unwrap().push((path, status));
+	let _ = stream.write_all(&response);
+}
+
+pub fn fixtures_dir() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR"))
+		.join("tests")
+		.join("fixtures")
+}
+
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+static SET_API_KEY: Once = Once::new();
+
+/// Holds the environment for one test, with an empty HTTP cache that's removed afterwards
+pub struct EnvGuard {
+	pub cache_dir: TempDir,
+	_guard: MutexGuard<'static, ()>,
+}
+
+impl Drop for EnvGuard {
+	fn drop(&mut self) {
+		set_offline(false);
+	}
+}
+
+/// Tests that touch the environment have to run one at a time, hold on to the guard for the whole test
+pub fn lock_env() -> EnvGuard {
+	let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+	SET_API_KEY.call_once(|| {
+		// SAFETY: every test that reads the environment holds TEST_LOCK, so nothing else is reading it right now
+		unsafe { std::env::set_var("CURSEFORGE_API_KEY", "fixture-key") };
+	});
+
+	let cache_dir = tempfile::tempdir().unwrap();
+	// SAFETY: as above
+	unsafe {
+		std::env::set_var("MODPACKR_CACHE_DIR", cache_dir.path());
+		std::env::remove_var("MODPACKR_CACHE_TTL");
+		for (var, _) in ENDPOINT_VARS {
+			std::env::remove_var(var);
+		}
+	}
+
+	EnvGuard {
+		cache_dir,
+		_guard: guard,
+	}
+}
+
+/// The environment variables for each endpoint, with the fixture server path they point at
+const ENDPOINT_VARS: [(&str, &str); 7] = [
+	("MODPACKR_MODRINTH_API", "modrinth"),
+	("MODPACKR_CURSEFORGE_API", "curseforge"),
+	("MODPACKR_FABRIC_API", "fabric"),
+	("MODPACKR_QUILT_API", "quilt"),
+	("MODPACKR_FORGE_MAVEN", "forge"),
+	("MODPACKR_NEOFORGE_MAVEN", "neoforge"),
+	("MODPACKR_MOJANG_API", "mojang"),
+];
+
+/// Points every endpoint at `server` through the environment, for code that runs without a project's config.toml
+/// (like import). Lasts until the next lock_env
+pub fn use_fixture_endpoints(server: &FixtureServer) {
+	for (var, path) in ENDPOINT_VARS {
+		// SAFETY: only called while holding the guard from lock_env
+		unsafe { std::env::set_var(var, format!("{}/{}", server.base_url, path)) };
+	}
+}
+
+/// Makes every request after this revalidate its cache entry, until the next lock_env
+pub fn expire_cache_immediately() {
+	// SAFETY: only called while holding the guard from lock_env
+	unsafe { std::env::set_var("MODPACKR_CACHE_TTL", "0") };
+}
+
+/// Creates a project in a temporary directory that talks to `server`, with the given mods
+pub fn create_project(server: &FixtureServer, mods: Vec<Mod>) -> TempDir {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("pack");
+	create_project_at_path(&path).unwrap();
+
+	let mut config = load_config(&path).unwrap();
+	config.endpoints = server.endpoints();
+	update_config(&path, &config).unwrap();
+
+	let mut modpack = load_modpack(&path).unwrap();
+	modpack.mods = mods;
+	update_modpack(&path, modpack).unwrap();
+
+	dir
+}
+
+/// Parses a JSON file inside an exported archive
+pub fn read_json_from_zip(archive: &Path, name: &str) -> serde_json::Value {
+	let mut zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+	serde_json::from_reader(zip.by_name(name).unwrap()).unwrap()
+}
+
+pub fn project_path(dir: &TempDir) -> PathBuf {
+	dir.path().join("pack")
+}
+
+/// Sets the loader and version like `check` would, without needing to run it
+pub fn set_target(path: &Path, loader: ModLoader, version: &str) {
+	let mut modpack = load_modpack(path).unwrap();
+	modpack.loader = Some(loader);
+	modpack.minecraft_version = Some(version.to_owned());
+	update_modpack(path, modpack).unwrap();
+}
+
+pub fn modrinth_mod(name: &str, id: &str, slug: &str) -> Mod {
+	Mod::new(
+		name,
+		vec![ProviderRef {
+			provider: "modrinth".to_owned(),
+			id: id.to_owned(),
+			slug: slug.to_owned(),
+			title: name.to_owned(),
+		}],
+	)
+}
+
+pub fn curseforge_mod(name: &str, id: u32, slug: &str) -> Mod {
+	Mod::new(
+		name,
+		vec![ProviderRef {
+			provider: "curseforge".to_owned(),
+			id: id.to_string(),
+			slug: slug.to_owned(),
+			title: name.to_owned(),
+		}],
+	)
+}
+
+/// The usual test pack: sodium and fabric api from modrinth plus JEI from curseforge, which only line up on fabric
+/// 1.20.1
+pub fn default_mods() -> Vec<Mod> {
+	vec![
+		modrinth_mod("Sodium", "AANobbMI", "sodium"),
+		modrinth_mod("Fabric API", "P7dR8mSH", "fabric-api"),
+		curseforge_mod("Just Enough Items", 238222, "jei"),
+	]
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{Mod, util::check},
+};
+
+#[test]
+fn check_reports_declared_incompatibilities() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			modrinth_mod("OptiFabric", "Z3bE9hTU", "optifabric"),
+		],
+	);
+
+	let result = check(&project_path(&dir)).unwrap();
+
+	assert_eq!(
+		result.conflicts,
+		vec![("Sodium".to_owned(), "OptiFabric".to_owned())]
+	);
+}
+
+#[test]
+fn incompatible_manual_mods_are_matched_by_name() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			Mod::new("optifabric", Vec::new()),
+		],
+	);
+
+	let result = check(&project_path(&dir)).unwrap();
+
+	assert_eq!(
+		result.conflicts,
+		vec![("Sodium".to_owned(), "optifabric".to_owned())]
+	);
+}
+
+#[test]
+fn incompatibilities_with_mods_outside_the_pack_are_ignored() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+
+	let result = check(&project_path(&dir)).unwrap();
+
+	assert!(result.conflicts.is_empty());
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Mod, ModLoader, ModVersions, Solver,
+		diagnostics::rank_targets,
+		mojang::VersionCatalogue,
+		util::{check, edit_mod, load_config, update_config},
+		version::MinecraftVersion,
+	},
+	std::collections::{BTreeMap, BTreeSet},
+};
+
+fn versions(list: &[&str]) -> BTreeSet<MinecraftVersion> {
+	list.iter().map(|v| MinecraftVersion::new(*v)).collect()
+}
+
+fn ranked(targets: &[modpackr::diagnostics::RankedTarget]) -> Vec<(ModLoader, &str, Vec<&str>)> {
+	targets
+		.iter()
+		.map(|t| {
+			(
+				t.loader.clone(),
+				t.version.as_str(),
+				t.excluded.iter().map(String::as_str).collect(),
+			)
+		})
+		.collect()
+}
+
+fn pack() -> BTreeMap<String, ModVersions> {
+	BTreeMap::from([
+		(
+			"A".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.20.1", "1.21"]),
+				..Default::default()
+			},
+		),
+		(
+			"B".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.20.1", "1.21"]),
+				..Default::default()
+			},
+		),
+		(
+			"C".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.19.2"]),
+				forge: versions(&["1.20.1"]),
+				..Default::default()
+			},
+		),
+	])
+}
+
+#[test]
+fn targets_are_ranked_by_supported_mods() {
+	let mods = vec![
+		Mod::new("A", vec![]),
+		Mod::new("B", vec![]),
+		Mod::new("C", vec![]),
+	];
+
+	let ranking = rank_targets(
+		&pack(),
+		&mods,
+		&VersionCatalogue::default(),
+		&ModLoader::ALL,
+		None,
+		false,
+	);
+
+	assert_eq!(
+		ranked(&ranking),
+		vec![
+			(ModLoader::Fabric, "1.21", vec!["C"]),
+			(ModLoader::Fabric, "1.20.1", vec!["C"]),
+			(ModLoader::Forge, "1.20.1", vec!["A", "B"]),
+			(ModLoader::Fabric, "1.19.2", vec!["A", "B"]),
+		]
+	);
+	assert_eq!(ranking[0].score, 2);
+	assert_eq!(
+		ranking[0].to_string(),
+		"fabric 1.21: 2 of 3 mods (score 2), without C"
+	);
+}
+
+#[test]
+fn priority_and_required_change_the_ranking() {
+	let mut c = Mod::new("C", vec![]);
+	c.priority = Some(5);
+	let mods = vec![Mod::new("A", vec![]), Mod::new("B", vec![]), c.clone()];
+
+	let ranking = rank_targets(
+		&pack(),
+		&mods,
+		&VersionCatalogue::default(),
+		&ModLoader::ALL,
+		None,
+		false,
+	);
+
+	assert_eq!(
+		ranked(&ranking)[..2],
+		[
+			(ModLoader::Forge, "1.20.1", vec!["A", "B"]),
+			(ModLoader::Fabric, "1.19.2", vec!["A", "B"]),
+		]
+	);
+
+	c.priority = None;
+	c.required = true;
+	let mods = vec![Mod::new("A", vec![]), Mod::new("B", vec![]), c];
+
+	let ranking = rank_targets(
+		&pack(),
+		&mods,
+		&VersionCatalogue::default(),
+		&ModLoader::ALL,
+		None,
+		false,
+	);
+
+	assert_eq!(
+		ranked(&ranking),
+		vec![
+			(ModLoader::Forge, "1.20.1", vec!["A", "B"]),
+			(ModLoader::Fabric, "1.19.2", vec!["A", "B"]),
+		]
+	);
+}
+
+#[test]
+fn equal_targets_follow_the_loader_order() {
+	let pack = BTreeMap::from([(
+		"A".to_owned(),
+		ModVersions {
+			fabric: versions(&["1.20.1"]),
+			forge: versions(&["1.20.1"]),
+			..Default::default()
+		},
+	)]);
+	let mods = vec![Mod::new("A", vec![])];
+
+	let ranking = rank_targets(
+		&pack,
+		&mods,
+		&VersionCatalogue::default(),
+		&[ModLoader::Forge, ModLoader::Fabric],
+		None,
+		false,
+	);
+
+	assert_eq!(
+		ranked(&ranking),
+		vec![
+			(ModLoader::Forge, "1.20.1", vec![]),
+			(ModLoader::Fabric, "1.20.1", vec![]),
+		]
+	);
+}
+
+#[test]
+fn check_ranks_targets_with_the_coverage_solver() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.solver = Solver::Coverage;
+	update_config(&path, &config).unwrap();
+	edit_mod(&path, "Sodium", |m| m.required = true).unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+	assert!(!result.ranking.is_empty());
+	assert!(
+		result
+			.ranking
+			.iter()
+			.all(|t| !t.excluded.contains(&"Sodium".to_owned()))
+	);
+	assert!(result.ranking[0].excluded.is_empty());
+	assert_eq!(
+		ranked(&result.ranking)[2],
+		(ModLoader::Fabric, "1.20.4", vec!["Just Enough Items"])
+	);
+}
+
+#[test]
+fn strict_check_does_not_rank() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+
+	let result = check(&project_path(&dir)).unwrap();
+
+	assert!(result.ranking.is_empty());
+}
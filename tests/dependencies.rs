@@ -0,0 +1,149 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader,
+		provider::ProviderRef,
+		util::{check, load_config, load_modpack, update_config},
+	},
+};
+
+#[test]
+fn check_adds_required_dependencies() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![modrinth_mod("Mod Menu", "mOgUt4GM", "modmenu")],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+	assert_eq!(
+		result.added_dependencies,
+		vec![("Fabric API".to_owned(), "Mod Menu".to_owned())]
+	);
+
+	let modpack = load_modpack(&path).unwrap();
+	let fabric_api = &modpack.mods[1];
+	assert_eq!(fabric_api.name, "Fabric API");
+	assert_eq!(fabric_api.required_by.as_deref(), Some("Mod Menu"));
+	assert_eq!(fabric_api.providers[0].id, "P7dR8mSH");
+	assert!(!modpack.mods[0].is_auto_added());
+}
+
+#[test]
+fn dependencies_on_a_version_are_looked_up() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![modrinth_mod("Mod Menu", "mOgUt4GM", "modmenu")],
+	);
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.version = Some("1.20.1".to_owned());
+	update_config(&path, &config).unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.added_dependencies,
+		vec![("Fabric API".to_owned(), "Mod Menu".to_owned())]
+	);
+	assert!(
+		server
+			.requests()
+			.contains(&"/modrinth/version/fabric-api-0.92.0-1.20.1".to_owned())
+	);
+}
+
+#[test]
+fn check_adds_curseforge_dependencies() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+	assert_eq!(
+		result.added_dependencies,
+		vec![("Fabric API".to_owned(), "Just Enough Items".to_owned())]
+	);
+
+	let modpack = load_modpack(&path).unwrap();
+	let fabric_api = modpack.mods[1].provider("curseforge").unwrap();
+	assert_eq!(fabric_api.id, "306612");
+	assert!(path.join("mods").join("Fabric API.ron").exists());
+}
+
+#[test]
+fn dependencies_from_another_provider_are_not_added_twice() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert!(result.added_dependencies.is_empty());
+	assert_eq!(load_modpack(&path).unwrap().mods.len(), 3);
+}
+
+#[test]
+fn slugs_only_match_on_the_same_provider() {
+	let jei = modrinth_mod("Just Enough Items", "u6dRKJwZ", "jei");
+	let project = |provider: &str, id: &str| ProviderRef {
+		provider: provider.to_owned(),
+		id: id.to_owned(),
+		slug: "jei".to_owned(),
+		title: "Jei".to_owned(),
+	};
+
+	assert!(jei.is_project(&project("modrinth", "u6dRKJwZ")));
+	assert!(jei.is_project(&project("modrinth", "other")));
+	assert!(!jei.is_project(&project("curseforge", "238222")));
+}
+
+#[test]
+fn mod_names_that_cant_be_file_names_still_check() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![modrinth_mod(
+			"Mod Menu: Fabric/Quilt",
+			"mOgUt4GM",
+			"modmenu",
+		)],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert!(result.target.is_some());
+	assert!(
+		path.join("mods")
+			.join("Mod Menu_ Fabric_Quilt.ron")
+			.is_file()
+	);
+	assert_eq!(
+		load_modpack(&path).unwrap().mods[0].name,
+		"Mod Menu: Fabric/Quilt"
+	);
+}
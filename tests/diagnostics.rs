@@ -0,0 +1,155 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader, ModVersions,
+		diagnostics::{Candidate, diagnose},
+		mojang::VersionCatalogue,
+		util::{check, load_config, load_modpack, update_config, update_modpack},
+		version::MinecraftVersion,
+	},
+	std::collections::{BTreeMap, BTreeSet},
+};
+
+fn versions(list: &[&str]) -> BTreeSet<MinecraftVersion> {
+	list.iter().map(|v| MinecraftVersion::new(*v)).collect()
+}
+
+fn candidate(version: &str, blockers: &[&str]) -> Candidate {
+	Candidate {
+		version: version.to_owned(),
+		blockers: blockers.iter().map(|b| b.to_string()).collect(),
+	}
+}
+
+#[test]
+fn diagnose_lists_the_blockers_of_each_version() {
+	let mods = BTreeMap::from([
+		(
+			"A".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.20.1", "1.21"]),
+				..Default::default()
+			},
+		),
+		(
+			"B".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.20.1"]),
+				..Default::default()
+			},
+		),
+		(
+			"C".to_owned(),
+			ModVersions {
+				fabric: versions(&["1.19.2", "1.21"]),
+				forge: versions(&["1.20.1"]),
+				..Default::default()
+			},
+		),
+	]);
+
+	let diagnostics = diagnose(
+		&mods,
+		&VersionCatalogue::default(),
+		&ModLoader::ALL,
+		None,
+		false,
+	);
+
+	assert_eq!(diagnostics.len(), 2);
+
+	let fabric = &diagnostics[0];
+	assert_eq!(fabric.loader, ModLoader::Fabric);
+	assert_eq!(
+		fabric.candidates,
+		vec![
+			candidate("1.21", &["B"]),
+			candidate("1.20.1", &["C"]),
+			candidate("1.19.2", &["A", "B"]),
+		]
+	);
+	assert_eq!(fabric.newest, Some(candidate("1.21", &["B"])));
+
+	let forge = &diagnostics[1];
+	assert_eq!(forge.loader, ModLoader::Forge);
+	assert_eq!(forge.candidates, vec![candidate("1.20.1", &["A", "B"])]);
+
+	// Loaders come in the order they're preferred, and only the ones given
+	let diagnostics = diagnose(
+		&mods,
+		&VersionCatalogue::default(),
+		&[ModLoader::Forge, ModLoader::Quilt, ModLoader::Fabric],
+		None,
+		false,
+	);
+	assert_eq!(
+		diagnostics
+			.iter()
+			.map(|d| d.loader.clone())
+			.collect::<Vec<_>>(),
+		vec![ModLoader::Forge, ModLoader::Fabric]
+	);
+}
+
+#[test]
+fn check_explains_a_failure() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.loader = Some(ModLoader::Fabric);
+	config.version = Some("1.20.4".to_owned());
+	update_config(&path, &config).unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(result.target, None);
+	assert_eq!(result.diagnostics.len(), 1);
+	assert_eq!(
+		result.diagnostics[0].candidates,
+		vec![candidate("1.20.4", &["Just Enough Items"])]
+	);
+	assert_eq!(
+		result.diagnostics[0].to_string(),
+		"fabric:\n\t1.20.4 is blocked by Just Enough Items\n\tRemoving Just Enough Items would make the newest version, 1.20.4, work"
+	);
+}
+
+#[test]
+fn removed_mods_no_longer_block_check() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.loader = Some(ModLoader::Fabric);
+	config.version = Some("1.20.4".to_owned());
+	update_config(&path, &config).unwrap();
+
+	assert_eq!(check(&path).unwrap().target, None);
+
+	// Its versions are still in mods/ from the check before
+	let mut modpack = load_modpack(&path).unwrap();
+	modpack.mods.retain(|m| m.name != "Just Enough Items");
+	update_modpack(&path, modpack).unwrap();
+	assert!(path.join("mods").join("Just Enough Items.ron").is_file());
+
+	assert_eq!(
+		check(&path).unwrap().target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+
+	config.version = Some("1.16.5".to_owned());
+	update_config(&path, &config).unwrap();
+
+	let result = check(&path).unwrap();
+	assert_eq!(
+		result.diagnostics[0].candidates,
+		vec![candidate("1.16.5", &["Fabric API", "Sodium"])]
+	);
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		LockedMod, Lockfile,
+		curseforge::fingerprint,
+		download::{Downloader, Store},
+		provider::ResolvedFile,
+		util::update_lockfile,
+	},
+	std::{collections::BTreeMap, fs},
+};
+
+const JEI_SHA1: &str = "43f45ea684902cb1207d47b03b859c7d945f2ffa";
+const FABRIC_API_SHA1: &str = "7f78e71ca7a397e36b3f874139c34b60c3def42d";
+
+fn fixture_file(server: &FixtureServer, name: &str, hashes: &[(&str, &str)]) -> ResolvedFile {
+	ResolvedFile {
+		file_name: name.to_owned(),
+		url: Some(format!("{}/files/{}", server.base_url, name)),
+		hashes: hashes
+			.iter()
+			.map(|(algorithm, hash)| (algorithm.to_string(), hash.to_string()))
+			.collect::<BTreeMap<_, _>>(),
+		..Default::default()
+	}
+}
+
+fn jei(server: &FixtureServer) -> ResolvedFile {
+	fixture_file(
+		server,
+		"jei-1.20.1-fabric-15.2.0.27.jar",
+		&[("sha1", JEI_SHA1)],
+	)
+}
+
+#[test]
+fn fingerprints_skip_whitespace() {
+	assert_eq!(fingerprint(b""), 1540447798);
+	assert_eq!(fingerprint(b"abcd"), 3376380438);
+	assert_eq!(fingerprint(b"a b\tc\r\nd"), fingerprint(b"abcd"));
+	assert_eq!(fingerprint(b"jei jar\n"), 990066065);
+}
+
+#[test]
+fn files_are_stored_by_hash_and_only_downloaded_once() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let store = Store::open().unwrap();
+	let downloader = Downloader::new(Store::open().unwrap(), 4);
+
+	let path = downloader.fetch(&jei(&server)).unwrap();
+	let requests = server.requests().len();
+	let again = downloader.fetch(&jei(&server)).unwrap();
+
+	assert_eq!(path, store.blob_path(JEI_SHA1).unwrap());
+	assert_eq!(again, path);
+	assert_eq!(fs::read_to_string(path).unwrap(), "jei jar\n");
+	assert_eq!(server.requests().len(), requests);
+}
+
+#[test]
+fn files_that_dont_match_their_hashes_are_thrown_away() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let store = Store::open().unwrap();
+	let downloader = Downloader::new(Store::open().unwrap(), 4);
+	let file = fixture_file(
+		&server,
+		"jei-1.20.1-fabric-15.2.0.27.jar",
+		&[("sha1", FABRIC_API_SHA1)],
+	);
+
+	let error = downloader.fetch(&file).unwrap_err().to_string();
+
+	assert!(error.contains("doesn't match its sha1 hash"), "{error}");
+	assert!(!store.blob_path(FABRIC_API_SHA1).unwrap().exists());
+	assert!(!store.partial_path(&file).unwrap().exists());
+}
+
+#[test]
+fn curseforge_fingerprints_are_verified() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let downloader = Downloader::new(Store::open().unwrap(), 4);
+	let name = "jei-1.20.1-fabric-15.2.0.27.jar";
+
+	let path = downloader
+		.fetch(&fixture_file(
+			&server,
+			name,
+			&[("fingerprint", "990066065")],
+		))
+		.unwrap();
+	assert_eq!(fs::read_to_string(path).unwrap(), "jei jar\n");
+
+	let error = downloader
+		.fetch(&fixture_file(&server, name, &[("fingerprint", "1")]))
+		.unwrap_err()
+		.to_string();
+	assert!(
+		error.contains("doesn't match its fingerprint hash"),
+		"{error}"
+	);
+}
+
+#[test]
+fn interrupted_downloads_carry_on_where_they_stopped() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let store = Store::open().unwrap();
+	let file = jei(&server);
+	let partial = store.partial_path(&file).unwrap();
+	fs::create_dir_all(partial.parent().unwrap()).unwrap();
+	fs::write(&partial, "jei ").unwrap();
+
+	let path = Downloader::new(Store::open().unwrap(), 4)
+		.fetch(&file)
+		.unwrap();
+
+	assert_eq!(fs::read_to_string(path).unwrap(), "jei jar\n");
+	assert_eq!(server.statuses(), vec![206]);
+	assert!(!partial.exists());
+}
+
+#[test]
+fn fetch_all_keeps_the_order_of_the_files() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let store = Store::open().unwrap();
+	let fabric_api = fixture_file(
+		&server,
+		"fabric-api-0.92.0+1.20.1.jar",
+		&[("sha1", FABRIC_API_SHA1)],
+	);
+	let jei = jei(&server);
+
+	let paths = Downloader::new(Store::open().unwrap(), 2)
+		.fetch_all(&[&jei, &fabric_api, &jei])
+		.unwrap();
+
+	assert_eq!(
+		paths,
+		vec![
+			store.blob_path(JEI_SHA1).unwrap(),
+			store.blob_path(FABRIC_API_SHA1).unwrap(),
+			store.blob_path(JEI_SHA1).unwrap()
+		]
+	);
+}
+
+#[test]
+fn files_without_a_url_cant_be_downloaded() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let file = ResolvedFile {
+		url: None,
+		..jei(&server)
+	};
+
+	let error = Downloader::new(Store::open().unwrap(), 4)
+		.fetch(&file)
+		.unwrap_err()
+		.to_string();
+
+	assert!(error.contains("no download URL"), "{error}");
+}
+
+#[test]
+fn gc_keeps_what_projects_have_locked() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	let store = Store::open().unwrap();
+
+	let downloader = Downloader::new(Store::open().unwrap(), 4);
+	downloader.fetch(&jei(&server)).unwrap();
+	store.insert(b"something nothing uses").unwrap();
+
+	update_lockfile(
+		&path,
+		&Lockfile {
+			mods: vec![LockedMod {
+				name: "Just Enough Items".to_owned(),
+				files: vec![jei(&server)],
+				..Default::default()
+			}],
+			..Default::default()
+		},
+	)
+	.unwrap();
+	store.add_root(&path).unwrap();
+
+	let report = store.gc().unwrap();
+
+	assert_eq!(report.removed, 1);
+	assert_eq!(report.freed, 22);
+	assert!(store.blob_path(JEI_SHA1).unwrap().is_file());
+
+	// Once the project is gone nothing is kept
+	drop(dir);
+	assert_eq!(store.gc().unwrap().removed, 1);
+	assert!(store.roots().is_empty());
+}
+
+#[test]
+fn hashes_that_arent_hex_never_become_paths() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let store = Store::open().unwrap();
+
+	assert!(store.blob_path("../../escape").is_err());
+	assert!(store.blob_path("éa").is_err());
+	assert!(store.blob_path("").is_err());
+
+	let file = fixture_file(
+		&server,
+		"jei-1.20.1-fabric-15.2.0.27.jar",
+		&[("sha1", "../../escape")],
+	);
+	assert!(store.partial_path(&file).is_err());
+	assert!(
+		Downloader::new(Store::open().unwrap(), 4)
+			.fetch(&file)
+			.is_err()
+	);
+	assert!(server.requests().is_empty());
+}
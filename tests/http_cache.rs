@@ -0,0 +1,78 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{ModLoader, http::set_offline, util::check},
+};
+
+#[test]
+fn second_check_is_served_from_the_cache() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+
+	check(&path).unwrap();
+	let requests = server.requests();
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+	assert_eq!(server.requests(), requests);
+	// Nothing is fetched twice, even within one check
+	let mut unique = requests.clone();
+	unique.sort();
+	unique.dedup();
+	assert_eq!(unique.len(), requests.len());
+}
+
+#[test]
+fn offline_check_uses_responses_cached_while_online() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let online = check(&path).unwrap();
+	let requests = server.requests().len();
+
+	set_offline(true);
+	let offline = check(&path).unwrap();
+
+	assert_eq!(online, offline);
+	assert_eq!(server.requests().len(), requests);
+}
+
+#[test]
+fn offline_without_a_cache_fails() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+
+	set_offline(true);
+	let error = check(&project_path(&dir)).unwrap_err().to_string();
+
+	assert!(error.contains("not in the cache"), "{error}");
+	assert!(server.requests().is_empty());
+}
+
+#[test]
+fn stale_entries_are_revalidated_with_their_etag() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	expire_cache_immediately();
+
+	let first = check(&path).unwrap();
+	let fetched = server.statuses().len();
+	let second = check(&path).unwrap();
+
+	assert_eq!(first, second);
+	// Everything was cached by the first check, so the second only revalidates
+	let statuses = server.statuses();
+	assert!(statuses.len() > fetched);
+	assert!(statuses[fetched..].iter().all(|status| *status == 304));
+}
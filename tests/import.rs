@@ -0,0 +1,279 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ManualJar, ModLoader,
+		provider::Channel,
+		util::{export, import_archive, load_config, load_modpack, update_config},
+	},
+	std::{fs, io::Write, path::Path},
+	zip::{ZipWriter, write::SimpleFileOptions},
+};
+
+/// Hashes of tests/fixtures/files/faithful-32x.zip
+const FAITHFUL_SHA1: &str = "52670e1e15d6a07e2da18bda39e8b8fc2fdfbd97";
+const FAITHFUL_SHA512: &str = "dfea486542dfabe797a4258ab66f4d8b3149762a4a3f6afc26aee2c99935a008db1112bf6cba4d0f506bd18e40211d0f97c0ee6839962367454011122642e0ae";
+
+/// Writes a zip with these entries, like the archives other tools export
+fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+	let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+	for (name, contents) in entries {
+		zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+		zip.write_all(contents.as_bytes()).unwrap();
+	}
+	zip.finish().unwrap();
+}
+
+#[test]
+fn modpackr_archives_round_trip() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	let mut config = load_config(&path).unwrap();
+	config.channel = Channel::Beta;
+	update_config(&path, &config).unwrap();
+	fs::create_dir_all(path.join("overrides").join("config")).unwrap();
+	fs::write(path.join("overrides").join("config").join("jei.toml"), "a").unwrap();
+
+	export(&path, false, false, true, false, None).unwrap();
+
+	let imported = dir.path().join("imported");
+	let manual_mods =
+		import_archive(&path.join("export").join("pack-1.0.0.modpackr"), &imported).unwrap();
+
+	assert!(manual_mods.is_empty());
+	assert_eq!(
+		load_modpack(&imported).unwrap(),
+		load_modpack(&path).unwrap()
+	);
+	assert_eq!(load_config(&imported).unwrap(), config);
+	assert_eq!(
+		fs::read_to_string(imported.join("overrides").join("config").join("jei.toml")).unwrap(),
+		"a"
+	);
+
+	// The project is there now, so importing over it again is refused
+	assert!(import_archive(&path.join("export").join("pack-1.0.0.modpackr"), &imported).is_err());
+}
+
+#[test]
+fn mrpacks_import_known_files_as_mods_and_download_the_rest() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	use_fixture_endpoints(&server);
+	let dir = tempfile::tempdir().unwrap();
+
+	// Sodium's hash is in the version_files fixture, the other jar isn't on modrinth
+	let index = serde_json::json!({
+		"formatVersion": 1,
+		"game": "minecraft",
+		"versionId": "2.0.0",
+		"name": "imported",
+		"files": [
+			{
+				"path": "mods/sodium-fabric-mc1.20.1-0.5.3.jar",
+				"hashes": { "sha1": "1".repeat(40), "sha512": "1".repeat(128) },
+				"downloads": [],
+				"fileSize": 1000000,
+			},
+			{
+				"path": "mods/secret-mod-1.0.jar",
+				"hashes": { "sha1": "9".repeat(40), "sha512": "9".repeat(128) },
+				"downloads": ["https://example.com/secret-mod-1.0.jar"],
+				"fileSize": 10,
+			},
+			{
+				"path": "resourcepacks/faithful-32x.zip",
+				"hashes": {
+					"sha1": FAITHFUL_SHA1,
+					"sha512": FAITHFUL_SHA512,
+				},
+				"env": { "client": "required", "server": "unsupported" },
+				"downloads": [format!("{}/files/faithful-32x.zip", server.base_url)],
+				"fileSize": 14,
+			},
+		],
+		"dependencies": { "minecraft": "1.20.1", "fabric-loader": "0.15.11" },
+	});
+	let archive = dir.path().join("imported.mrpack");
+	write_zip(
+		&archive,
+		&[
+			("modrinth.index.json", &index.to_string()),
+			("overrides/config/sodium.json", "{}"),
+			("client-overrides/options.txt", "gui"),
+		],
+	);
+
+	let path = dir.path().join("pack");
+	let manual_mods = import_archive(&archive, &path).unwrap();
+
+	assert_eq!(manual_mods, vec!["secret-mod-1.0".to_owned()]);
+
+	let modpack = load_modpack(&path).unwrap();
+	assert_eq!(modpack.name, "imported");
+	assert_eq!(modpack.version, "2.0.0");
+	assert_eq!(modpack.loader, Some(ModLoader::Fabric));
+	assert_eq!(modpack.minecraft_version.as_deref(), Some("1.20.1"));
+	assert_eq!(modpack.mods.len(), 2);
+	assert_eq!(modpack.mods[0].name, "Sodium");
+	assert_eq!(modpack.mods[0].provider("modrinth").unwrap().id, "AANobbMI");
+	assert!(modpack.mods[1].is_manual());
+	assert_eq!(
+		modpack.mods[1].jar,
+		Some(ManualJar {
+			file_name: "secret-mod-1.0.jar".to_owned(),
+			sha1: "9".repeat(40),
+			url: Some("https://example.com/secret-mod-1.0.jar".to_owned()),
+			redistributable: false,
+		})
+	);
+
+	assert!(
+		path.join("overrides")
+			.join("config")
+			.join("sodium.json")
+			.is_file()
+	);
+	assert!(path.join("client-overrides").join("options.txt").is_file());
+	// Files outside mods/ are downloaded into the overrides of the side that uses them
+	assert_eq!(
+		fs::read_to_string(
+			path.join("client-overrides")
+				.join("resourcepacks")
+				.join("faithful-32x.zip")
+		)
+		.unwrap(),
+		"resource pack\n"
+	);
+}
+
+#[test]
+fn curseforge_zips_import_with_their_loader() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	use_fixture_endpoints(&server);
+	let dir = tempfile::tempdir().unwrap();
+
+	for (id, loader) in [
+		("fabric-0.15.11", ModLoader::Fabric),
+		("quilt-0.26.0", ModLoader::Quilt),
+		("forge-47.2.0", ModLoader::Forge),
+		("neoforge-20.4.237", ModLoader::Neoforge),
+	] {
+		// Fabric API is in the fixtures, project 238222 isn't looked up there so it becomes a manual mod
+		let manifest = serde_json::json!({
+			"minecraft": {
+				"version": "1.20.1",
+				"modLoaders": [{ "id": id, "primary": true }],
+			},
+			"manifestType": "minecraftModpack",
+			"manifestVersion": 1,
+			"name": "imported",
+			"version": "3.0.0",
+			"author": "someone",
+			"files": [
+				{ "projectID": 306612, "fileID": 4712866 },
+				{ "projectID": 238222, "fileID": 5101366 },
+			],
+			"overrides": "extra",
+		});
+		let archive = dir.path().join(format!("{id}.zip"));
+		write_zip(
+			&archive,
+			&[
+				("manifest.json", &manifest.to_string()),
+				("extra/config/jei.toml", "a"),
+			],
+		);
+
+		let path = dir.path().join(id);
+		let manual_mods = import_archive(&archive, &path).unwrap();
+
+		assert_eq!(manual_mods, vec!["curseforge-238222".to_owned()], "{id}");
+
+		let modpack = load_modpack(&path).unwrap();
+		assert_eq!(modpack.loader, Some(loader), "{id}");
+		assert_eq!(modpack.minecraft_version.as_deref(), Some("1.20.1"));
+		assert_eq!(modpack.author, "someone");
+		assert_eq!(modpack.mods[0].name, "Fabric API");
+		assert_eq!(modpack.mods[0].provider("curseforge").unwrap().id, "306612");
+		assert!(
+			path.join("overrides")
+				.join("config")
+				.join("jei.toml")
+				.is_file()
+		);
+	}
+}
+
+#[test]
+fn curseforge_zips_without_an_overrides_folder_use_the_usual_one() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	use_fixture_endpoints(&server);
+	let dir = tempfile::tempdir().unwrap();
+
+	let manifest = serde_json::json!({
+		"minecraft": {
+			"version": "1.20.1",
+			"modLoaders": [{ "id": "fabric-0.15.11", "primary": true }],
+		},
+		"manifestType": "minecraftModpack",
+		"manifestVersion": 1,
+		"name": "imported",
+		"version": "3.0.0",
+		"author": "someone",
+		"files": [],
+		"overrides": "",
+	});
+	let archive = dir.path().join("imported.zip");
+	write_zip(
+		&archive,
+		&[
+			("manifest.json", &manifest.to_string()),
+			("overrides/config/jei.toml", "a"),
+		],
+	);
+
+	let path = dir.path().join("pack");
+	import_archive(&archive, &path).unwrap();
+
+	let overrides = path.join("overrides");
+	assert!(overrides.join("config").join("jei.toml").is_file());
+	assert!(!overrides.join("manifest.json").exists());
+	assert!(!overrides.join("overrides").exists());
+}
+
+#[test]
+fn mrpack_files_that_dont_match_their_hash_fail_the_import() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	use_fixture_endpoints(&server);
+	let dir = tempfile::tempdir().unwrap();
+
+	let index = serde_json::json!({
+		"formatVersion": 1,
+		"game": "minecraft",
+		"versionId": "1.0.0",
+		"name": "imported",
+		"files": [{
+			"path": "resourcepacks/faithful-32x.zip",
+			"hashes": { "sha1": "0".repeat(40), "sha512": FAITHFUL_SHA512 },
+			"downloads": [format!("{}/files/faithful-32x.zip", server.base_url)],
+			"fileSize": 14,
+		}],
+		"dependencies": { "minecraft": "1.20.1", "fabric-loader": "0.15.11" },
+	});
+	let archive = dir.path().join("imported.mrpack");
+	write_zip(&archive, &[("modrinth.index.json", &index.to_string())]);
+
+	let error = import_archive(&archive, &dir.path().join("pack"))
+		.unwrap_err()
+		.to_string();
+	assert!(error.contains("doesn't match its sha1 hash"), "{error}");
+}
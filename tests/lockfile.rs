@@ -0,0 +1,171 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader,
+		util::{
+			export, load_lockfile, load_modpack, lock, update, update_lockfile, update_modpack,
+		},
+	},
+	std::path::Path,
+};
+
+/// Points the locked curseforge file for JEI somewhere else, like a newer file being published would
+fn tamper_jei(path: &Path, file_id: &str) {
+	let mut lockfile = load_lockfile(path).unwrap().unwrap();
+	let jei = lockfile
+		.mods
+		.iter_mut()
+		.find(|m| m.name == "Just Enough Items")
+		.unwrap();
+	jei.files[0].file_id = file_id.to_owned();
+	update_lockfile(path, &lockfile).unwrap();
+}
+
+fn locked_file_id(path: &Path, name: &str, provider: &str) -> String {
+	let lockfile = load_lockfile(path).unwrap().unwrap();
+	lockfile
+		.get(name)
+		.and_then(|m| m.file(provider))
+		.map(|file| file.file_id.clone())
+		.unwrap()
+}
+
+#[test]
+fn lock_records_every_file() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	let lockfile = lock(&path).unwrap();
+
+	assert_eq!(lockfile, load_lockfile(&path).unwrap().unwrap());
+	assert_eq!(lockfile.loader, ModLoader::Fabric);
+	assert_eq!(lockfile.minecraft_version, "1.20.1");
+	assert_eq!(lockfile.loader_version, "fabric-0.15.11");
+	assert_eq!(lockfile.mods.len(), 3);
+
+	let sodium = lockfile.get("Sodium").unwrap().file("modrinth").unwrap();
+	assert_eq!(sodium.file_id, "sodium-0.5.3-1.20.1");
+	assert_eq!(sodium.file_name, "sodium-fabric-mc1.20.1-0.5.3.jar");
+	assert_eq!(
+		sodium.hashes["sha1"],
+		"1111111111111111111111111111111111111111"
+	);
+
+	let jei = lockfile
+		.get("Just Enough Items")
+		.unwrap()
+		.file("curseforge")
+		.unwrap();
+	assert_eq!(jei.project_id, "238222");
+	assert_eq!(jei.file_id, "4712866");
+}
+
+#[test]
+fn exports_ship_the_locked_files() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	lock(&path).unwrap();
+	tamper_jei(&path, "4000000");
+	export(&path, true, false, false, false, None).unwrap();
+
+	let manifest = read_json_from_zip(
+		&path.join("export").join("pack-1.0.0-curseforge.zip"),
+		"manifest.json",
+	);
+	assert_eq!(manifest["files"][0]["fileID"], 4000000);
+}
+
+#[test]
+fn lock_keeps_existing_entries_and_adds_new_mods() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	lock(&path).unwrap();
+	tamper_jei(&path, "4000000");
+
+	let mut modpack = load_modpack(&path).unwrap();
+	modpack
+		.mods
+		.push(modrinth_mod("Sodium", "AANobbMI", "sodium"));
+	update_modpack(&path, modpack).unwrap();
+
+	let lockfile = lock(&path).unwrap();
+
+	assert_eq!(lockfile.mods.len(), 2);
+	assert_eq!(
+		locked_file_id(&path, "Just Enough Items", "curseforge"),
+		"4000000"
+	);
+	assert_eq!(
+		locked_file_id(&path, "Sodium", "modrinth"),
+		"sodium-0.5.3-1.20.1"
+	);
+}
+
+#[test]
+fn update_only_refreshes_the_named_mods() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	lock(&path).unwrap();
+	tamper_jei(&path, "4000000");
+	let mut lockfile = load_lockfile(&path).unwrap().unwrap();
+	lockfile.mods[0].files[0].file_id = "old-sodium".to_owned();
+	update_lockfile(&path, &lockfile).unwrap();
+
+	update(&path, &["Just Enough Items".to_owned()]).unwrap();
+
+	assert_eq!(
+		locked_file_id(&path, "Just Enough Items", "curseforge"),
+		"4712866"
+	);
+	assert_eq!(locked_file_id(&path, "Sodium", "modrinth"), "old-sodium");
+
+	update(&path, &[]).unwrap();
+
+	assert_eq!(
+		locked_file_id(&path, "Sodium", "modrinth"),
+		"sodium-0.5.3-1.20.1"
+	);
+}
+
+#[test]
+fn changing_the_target_resolves_everything_again() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	lock(&path).unwrap();
+	let mut lockfile = load_lockfile(&path).unwrap().unwrap();
+	lockfile.mods[0].files[0].file_id = "old-sodium".to_owned();
+	update_lockfile(&path, &lockfile).unwrap();
+	set_target(&path, ModLoader::Quilt, "1.20.1");
+	let lockfile = lock(&path).unwrap();
+
+	assert_eq!(lockfile.loader, ModLoader::Quilt);
+	assert_eq!(lockfile.loader_version, "quilt-0.26.0");
+	assert_eq!(
+		locked_file_id(&path, "Sodium", "modrinth"),
+		"sodium-0.5.3-1.20.1"
+	);
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader, Side, Support,
+		download::Store,
+		util::{JarSource, add_mod, bundle_jar, edit_mod, export, load_modpack},
+	},
+	std::{fs, fs::File, path::Path},
+	zip::ZipArchive,
+};
+
+/// The contents and sha1 of tests/fixtures/files/jei-1.20.1-fabric-15.2.0.27.jar
+const JEI_JAR: &str = "jei jar\n";
+const JEI_SHA1: &str = "43f45ea684902cb1207d47b03b859c7d945f2ffa";
+
+/// Writes a jar next to the project and bundles it with a new manual mod called OptiFine
+fn add_optifine(path: &Path, redistributable: bool) {
+	let source = path.parent().unwrap().join("OptiFine_1.20.1.jar");
+	fs::write(&source, "optifine jar\n").unwrap();
+
+	let mut jar = bundle_jar(
+		path,
+		&JarSource::new(source.display().to_string(), None).unwrap(),
+	)
+	.unwrap();
+	jar.redistributable = redistributable;
+	add_mod(
+		path,
+		"OptiFine",
+		None::<String>,
+		None::<String>,
+		true,
+		Some(jar),
+	)
+	.unwrap();
+}
+
+fn zip_contains(archive: &Path, name: &str) -> bool {
+	let zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+	zip.index_for_name(name).is_some()
+}
+
+#[test]
+fn local_jars_are_copied_into_the_project() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+
+	add_optifine(&path, false);
+
+	assert_eq!(
+		fs::read_to_string(path.join("jars").join("OptiFine_1.20.1.jar")).unwrap(),
+		"optifine jar\n"
+	);
+	let modpack = load_modpack(&path).unwrap();
+	let jar = modpack.mods[0].jar.as_ref().unwrap();
+	assert_eq!(jar.file_name, "OptiFine_1.20.1.jar");
+	assert_eq!(jar.sha1, "c53f6b679977b9824944d97b9a26102e459a0d97");
+	assert_eq!(jar.url, None);
+	assert!(!modpack.mods[0].is_bundled());
+}
+
+#[test]
+fn downloaded_jars_are_checked_against_their_hash() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	let url = format!("{}/files/jei-1.20.1-fabric-15.2.0.27.jar", server.base_url);
+
+	let jar = bundle_jar(
+		&path,
+		&JarSource::new(url.clone(), Some(JEI_SHA1.to_owned())).unwrap(),
+	)
+	.unwrap();
+	assert_eq!(jar.file_name, "jei-1.20.1-fabric-15.2.0.27.jar");
+	assert_eq!(jar.sha1, JEI_SHA1);
+	assert_eq!(jar.url, Some(url.clone()));
+	assert_eq!(
+		fs::read_to_string(path.join("jars").join(&jar.file_name)).unwrap(),
+		JEI_JAR
+	);
+	// It went through the store like every other download
+	assert!(
+		Store::open()
+			.unwrap()
+			.blob_path(JEI_SHA1)
+			.unwrap()
+			.is_file()
+	);
+
+	let wrong = JarSource::new(url.clone(), Some("0".repeat(40))).unwrap();
+	let error = bundle_jar(&path, &wrong).unwrap_err().to_string();
+	assert!(error.contains("doesn't match its sha1 hash"), "{error}");
+
+	assert!(JarSource::new(url, None).is_err());
+}
+
+#[test]
+fn only_manual_mods_can_have_a_jar() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	fs::write(path.join("a.jar"), "a").unwrap();
+	let jar = bundle_jar(&path, &JarSource::Path(path.join("a.jar"))).unwrap();
+
+	assert!(
+		add_mod(
+			&path,
+			"Sodium",
+			None::<String>,
+			Some("https://modrinth.com/mod/sodium"),
+			false,
+			Some(jar),
+		)
+		.is_err()
+	);
+	assert!(load_modpack(&path).unwrap().mods.is_empty());
+}
+
+#[test]
+fn redistributable_jars_are_exported_as_overrides() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	add_optifine(&path, true);
+
+	export(&path, true, false, false, false, None).unwrap();
+	export(&path, false, true, false, false, None).unwrap();
+	export(&path, false, false, true, false, None).unwrap();
+
+	let export_dir = path.join("export");
+	assert!(zip_contains(
+		&export_dir.join("pack-1.0.0-curseforge.zip"),
+		"overrides/mods/OptiFine_1.20.1.jar"
+	));
+	assert!(zip_contains(
+		&export_dir.join("pack-1.0.0.mrpack"),
+		"overrides/mods/OptiFine_1.20.1.jar"
+	));
+	// The modpackr format is the project itself, so the jar stays where the project keeps it
+	assert!(zip_contains(
+		&export_dir.join("pack-1.0.0.modpackr"),
+		"jars/OptiFine_1.20.1.jar"
+	));
+}
+
+#[test]
+fn jars_are_left_out_until_redistribution_is_confirmed() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	add_optifine(&path, false);
+
+	export(&path, false, true, false, false, None).unwrap();
+	let mrpack = path.join("export").join("pack-1.0.0.mrpack");
+	assert!(!zip_contains(&mrpack, "overrides/mods/OptiFine_1.20.1.jar"));
+
+	edit_mod(&path, "OptiFine", |m| {
+		m.jar.as_mut().unwrap().redistributable = true;
+		m.server = Some(Support::Unsupported);
+	})
+	.unwrap();
+
+	export(&path, false, true, false, false, None).unwrap();
+	assert!(zip_contains(&mrpack, "overrides/mods/OptiFine_1.20.1.jar"));
+
+	// Client only, so the server export doesn't get it
+	export(&path, false, true, false, false, Some(Side::Server)).unwrap();
+	assert!(!zip_contains(
+		&path.join("export").join("pack-1.0.0-server.mrpack"),
+		"overrides/mods/OptiFine_1.20.1.jar"
+	));
+}
+
+#[test]
+fn jars_that_changed_since_they_were_added_arent_exported() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	add_optifine(&path, true);
+
+	fs::write(
+		path.join("jars").join("OptiFine_1.20.1.jar"),
+		"something else",
+	)
+	.unwrap();
+
+	let error = export(&path, false, true, false, false, None)
+		.unwrap_err()
+		.to_string();
+	assert!(error.contains("doesn't match its sha1 hash"), "{error}");
+}
+
+#[test]
+fn two_manual_mods_cant_bring_jars_with_the_same_name() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	add_optifine(&path, false);
+
+	let source = path.parent().unwrap().join("OptiFine_1.20.1.jar");
+	let error = bundle_jar(
+		&path,
+		&JarSource::new(source.display().to_string(), None).unwrap(),
+	)
+	.unwrap_err()
+	.to_string();
+	assert!(
+		error.contains("OptiFine already comes with a jar"),
+		"{error}"
+	);
+
+	let jar = load_modpack(&path).unwrap().mods[0].jar.clone();
+	assert!(add_mod(&path, "Other", None::<String>, None::<String>, true, jar).is_err());
+	assert_eq!(load_modpack(&path).unwrap().mods.len(), 1);
+	// OptiFine keeps its jar
+	assert!(path.join("jars").join("OptiFine_1.20.1.jar").exists());
+}
+
+#[test]
+fn a_rejected_mod_leaves_no_jar_behind() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	let source = path.parent().unwrap().join("OptiFine_1.20.1.jar");
+	fs::write(&source, "optifine jar\n").unwrap();
+
+	let jar = bundle_jar(
+		&path,
+		&JarSource::new(source.display().to_string(), None).unwrap(),
+	)
+	.unwrap();
+	assert!(path.join("jars").join("OptiFine_1.20.1.jar").exists());
+
+	// A manual mod can't also be on a provider
+	assert!(
+		add_mod(
+			&path,
+			"OptiFine",
+			Some("optifine"),
+			None::<String>,
+			true,
+			Some(jar),
+		)
+		.is_err()
+	);
+
+	assert!(!path.join("jars").join("OptiFine_1.20.1.jar").exists());
+	assert!(load_modpack(&path).unwrap().mods.is_empty());
+}
+
+#[test]
+fn two_mods_cant_share_a_name() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+	add_optifine(&path, false);
+
+	let error = add_mod(
+		&path,
+		"OptiFine",
+		None::<String>,
+		None::<String>,
+		true,
+		None,
+	)
+	.unwrap_err()
+	.to_string();
+
+	assert!(
+		error.contains("already is a mod called OptiFine"),
+		"{error}"
+	);
+	assert_eq!(load_modpack(&path).unwrap().mods.len(), 1);
+	// The jar belongs to the mod that's already there
+	assert!(path.join("jars").join("OptiFine_1.20.1.jar").exists());
+}
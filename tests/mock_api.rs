@@ -0,0 +1,175 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Mod, ModLoader,
+		util::{check, export, load_config, load_modpack, update_config},
+	},
+};
+
+#[test]
+fn check_finds_the_version_every_mod_supports() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+	assert!(result.manual_mods.is_empty());
+
+	let modpack = load_modpack(&path).unwrap();
+	assert_eq!(modpack.loader, Some(ModLoader::Fabric));
+	assert_eq!(modpack.minecraft_version.as_deref(), Some("1.20.1"));
+	assert!(path.join("mods").join("Sodium.ron").exists());
+}
+
+#[test]
+fn check_uses_the_loader_from_config() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.loader = Some(ModLoader::Quilt);
+	update_config(&path, &config).unwrap();
+
+	let result = check(&path).unwrap();
+
+	// Sodium 1.20.4 is only published for fabric, which quilt loads
+	assert_eq!(result.target, Some((ModLoader::Quilt, "1.20.4".to_owned())));
+}
+
+#[test]
+fn check_reads_every_page_of_curseforge_files() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Fabric API", 306612, "fabric-api")],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	// The 1.19.2 file is only on the second page
+	assert!(
+		result
+			.compatible
+			.contains(&(ModLoader::Fabric, "1.19.2".to_owned()))
+	);
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+}
+
+#[test]
+fn check_reports_mods_without_a_provider() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![
+			modrinth_mod("Sodium", "AANobbMI", "sodium"),
+			Mod::new("OptiFine", Vec::new()),
+		],
+	);
+	let path = project_path(&dir);
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+	assert_eq!(result.manual_mods, vec!["OptiFine".to_owned()]);
+}
+
+#[test]
+fn export_modrinth_writes_a_modrinth_index() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	export(&path, false, true, false, false, None).unwrap();
+
+	let index = read_json_from_zip(
+		&path.join("export").join("pack-1.0.0.mrpack"),
+		"modrinth.index.json",
+	);
+
+	assert_eq!(index["formatVersion"], 1);
+	assert_eq!(index["dependencies"]["minecraft"], "1.20.1");
+	assert_eq!(index["dependencies"]["fabric-loader"], "0.15.11");
+
+	let files = index["files"].as_array().unwrap();
+	assert_eq!(files.len(), 2);
+
+	let sodium = &files[0];
+	assert_eq!(sodium["path"], "mods/sodium-fabric-mc1.20.1-0.5.3.jar");
+	assert_eq!(
+		sodium["hashes"]["sha1"],
+		"1111111111111111111111111111111111111111"
+	);
+	assert_eq!(sodium["fileSize"], 1000000);
+	assert_eq!(sodium["env"]["client"], "required");
+	assert_eq!(sodium["env"]["server"], "unsupported");
+	assert_eq!(
+		sodium["downloads"][0],
+		"https://cdn.modrinth.com/data/AANobbMI/versions/sodium-0.5.3-1.20.1/sodium-fabric-mc1.20.1-0.5.3.jar"
+	);
+
+	assert_eq!(files[1]["path"], "mods/fabric-api-0.92.0+1.20.1.jar");
+}
+
+#[test]
+fn export_curseforge_writes_a_manifest() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	export(&path, true, false, false, false, None).unwrap();
+
+	let manifest = read_json_from_zip(
+		&path.join("export").join("pack-1.0.0-curseforge.zip"),
+		"manifest.json",
+	);
+
+	assert_eq!(manifest["minecraft"]["version"], "1.20.1");
+	assert_eq!(
+		manifest["minecraft"]["modLoaders"][0]["id"],
+		"fabric-0.15.11"
+	);
+	assert_eq!(
+		manifest["files"],
+		serde_json::json!([{ "projectID": 238222, "fileID": 4712866 }])
+	);
+}
+
+#[test]
+fn requests_go_to_the_configured_endpoints() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+
+	check(&project_path(&dir)).unwrap();
+
+	assert_eq!(
+		server.requests(),
+		vec![
+			"/mojang/mc/game/version_manifest_v2.json".to_owned(),
+			"/modrinth/project/AANobbMI/version".to_owned()
+		]
+	);
+}
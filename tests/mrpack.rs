@@ -0,0 +1,67 @@
+use {
+	modpackr::{
+		ModLoader,
+		modrinth::{
+			ModrinthEnv, ModrinthHashes, ModrinthIndex, ModrinthIndexFile, modrinth_dependency_key,
+		},
+	},
+	std::collections::BTreeMap,
+};
+
+#[test]
+fn modrinth_index_matches_the_mrpack_format() {
+	let index = ModrinthIndex {
+		format_version: 1,
+		game: "minecraft".to_owned(),
+		version_id: "1.0.0".to_owned(),
+		name: "pack".to_owned(),
+		files: vec![ModrinthIndexFile {
+			path: "mods/sodium-fabric-mc1.20.1-0.5.3.jar".to_owned(),
+			hashes: ModrinthHashes {
+				sha1: "1".repeat(40),
+				sha512: "2".repeat(128),
+			},
+			env: Some(ModrinthEnv {
+				client: "required".to_owned(),
+				server: "unsupported".to_owned(),
+			}),
+			downloads: vec!["https://cdn.modrinth.com/sodium.jar".to_owned()],
+			file_size: 1000000,
+		}],
+		dependencies: BTreeMap::from([
+			("minecraft".to_owned(), "1.20.1".to_owned()),
+			(
+				modrinth_dependency_key(&ModLoader::Fabric).to_owned(),
+				"0.15.11".to_owned(),
+			),
+		]),
+	};
+
+	let json = serde_json::to_value(&index).unwrap();
+
+	assert_eq!(
+		json,
+		serde_json::json!({
+			"formatVersion": 1,
+			"game": "minecraft",
+			"versionId": "1.0.0",
+			"name": "pack",
+			"files": [{
+				"path": "mods/sodium-fabric-mc1.20.1-0.5.3.jar",
+				"hashes": { "sha1": "1".repeat(40), "sha512": "2".repeat(128) },
+				"env": { "client": "required", "server": "unsupported" },
+				"downloads": ["https://cdn.modrinth.com/sodium.jar"],
+				"fileSize": 1000000,
+			}],
+			"dependencies": { "minecraft": "1.20.1", "fabric-loader": "0.15.11" },
+		})
+	);
+}
+
+#[test]
+fn loaders_use_their_modrinth_dependency_keys() {
+	assert_eq!(modrinth_dependency_key(&ModLoader::Fabric), "fabric-loader");
+	assert_eq!(modrinth_dependency_key(&ModLoader::Quilt), "quilt-loader");
+	assert_eq!(modrinth_dependency_key(&ModLoader::Forge), "forge");
+	assert_eq!(modrinth_dependency_key(&ModLoader::Neoforge), "neoforge");
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use {common::*, modpackr::neoforge::get_latest_neoforge_version};
+
+fn latest(server: &FixtureServer, mc_version: &str) -> anyhow::Result<String> {
+	get_latest_neoforge_version(&format!("{}/neoforge", server.base_url), mc_version)
+}
+
+#[test]
+fn neoforge_versions_drop_the_leading_one() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+
+	assert_eq!(latest(&server, "1.21.1").unwrap(), "neoforge-21.1.77");
+	assert_eq!(latest(&server, "1.21").unwrap(), "neoforge-21.0.167");
+	assert_eq!(latest(&server, "1.20.4").unwrap(), "neoforge-20.4.237");
+}
+
+#[test]
+fn neoforge_1_20_1_comes_from_the_old_forge_artifact() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+
+	assert_eq!(latest(&server, "1.20.1").unwrap(), "neoforge-47.1.106");
+	assert_eq!(
+		server.requests(),
+		vec!["/neoforge/net/neoforged/forge/maven-metadata.xml".to_owned()]
+	);
+}
+
+#[test]
+fn neoforge_betas_are_never_picked() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+
+	// 21.1.80-beta is newer than 21.1.77 and 21.10.5-beta only shares the start of the prefix
+	assert_eq!(latest(&server, "1.21.1").unwrap(), "neoforge-21.1.77");
+
+	// 20.5 has no versions at all, not even betas
+	let error = latest(&server, "1.20.5").unwrap_err().to_string();
+	assert!(
+		error.contains("No stable neoforge versions for 1.20.5"),
+		"{error}"
+	);
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader, OverrideFilter, Side,
+		util::{export, load_config, update_config},
+	},
+	std::{
+		fs::{self, File},
+		io::Read,
+		path::Path,
+	},
+	zip::ZipArchive,
+};
+
+/// Writes `contents` to `name` inside the project, creating the folders it's in
+fn write(path: &Path, name: &str, contents: &str) {
+	let file = path.join(name);
+	fs::create_dir_all(file.parent().unwrap()).unwrap();
+	fs::write(file, contents).unwrap();
+}
+
+/// The files in a zip, without the folder entries
+fn zip_files(archive: &Path) -> Vec<String> {
+	let zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+	let mut names = zip
+		.file_names()
+		.filter(|name| !name.ends_with('/'))
+		.map(str::to_owned)
+		.collect::<Vec<_>>();
+	names.sort();
+	names
+}
+
+fn read_from_zip(archive: &Path, name: &str) -> String {
+	let mut zip = ZipArchive::new(File::open(archive).unwrap()).unwrap();
+	let mut contents = String::new();
+	zip.by_name(name)
+		.unwrap()
+		.read_to_string(&mut contents)
+		.unwrap();
+	contents
+}
+
+/// A project with a file in every override folder, plus the kind of files that shouldn't be shipped
+fn project_with_overrides(server: &FixtureServer) -> tempfile::TempDir {
+	let dir = create_project(server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	write(&path, "overrides/config/jei.toml", "both");
+	write(&path, "overrides/options.txt", "mine");
+	write(&path, "overrides/logs/latest.log", "log");
+	write(&path, "client-overrides/config/jei.toml", "client");
+	write(&path, "client-overrides/resourcepacks/pack.zip", "pack");
+	write(&path, "server-overrides/server.properties", "server");
+
+	dir
+}
+
+#[test]
+fn new_projects_have_override_folders() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, Vec::new());
+	let path = project_path(&dir);
+
+	for folder in ["overrides", "client-overrides", "server-overrides"] {
+		assert!(path.join(folder).is_dir(), "{folder}");
+	}
+}
+
+#[test]
+fn override_filters_match_names_and_paths() {
+	let filter = OverrideFilter::default();
+	assert!(filter.allows("config/jei.toml"));
+	assert!(!filter.allows("options.txt"));
+	assert!(!filter.allows("logs/latest.log"));
+
+	let filter = OverrideFilter {
+		include: vec!["config/*".to_owned(), "saves".to_owned()],
+		exclude: vec!["*.bak".to_owned()],
+	};
+	assert!(filter.allows("config/jei.toml"));
+	assert!(filter.allows("saves/world/level.dat"));
+	assert!(!filter.allows("config/jei.toml.bak"));
+	assert!(!filter.allows("resourcepacks/pack.zip"));
+	// Globs with a `/` match the whole path, not a folder further down
+	assert!(!filter.allows("mods/config/a.toml"));
+}
+
+#[test]
+fn modrinth_exports_keep_every_override_folder() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = project_with_overrides(&server);
+	let path = project_path(&dir);
+
+	export(&path, false, true, false, false, None).unwrap();
+
+	assert_eq!(
+		zip_files(&path.join("export").join("pack-1.0.0.mrpack")),
+		vec![
+			"client-overrides/config/jei.toml",
+			"client-overrides/resourcepacks/pack.zip",
+			"modrinth.index.json",
+			"overrides/config/jei.toml",
+			"server-overrides/server.properties",
+		]
+	);
+
+	export(&path, false, true, false, false, Some(Side::Server)).unwrap();
+
+	assert_eq!(
+		zip_files(&path.join("export").join("pack-1.0.0-server.mrpack")),
+		vec![
+			"modrinth.index.json",
+			"overrides/config/jei.toml",
+			"server-overrides/server.properties",
+		]
+	);
+}
+
+#[test]
+fn curseforge_exports_merge_the_side_folder_into_overrides() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = project_with_overrides(&server);
+	let path = project_path(&dir);
+
+	export(&path, true, false, false, false, None).unwrap();
+
+	let archive = path.join("export").join("pack-1.0.0-curseforge.zip");
+	assert_eq!(
+		zip_files(&archive),
+		vec![
+			"manifest.json",
+			"overrides/config/jei.toml",
+			"overrides/resourcepacks/pack.zip",
+		]
+	);
+	// The client's copy replaces the one for both sides
+	assert_eq!(
+		read_from_zip(&archive, "overrides/config/jei.toml"),
+		"client"
+	);
+	assert_eq!(
+		read_json_from_zip(&archive, "manifest.json")["overrides"],
+		"overrides"
+	);
+
+	export(&path, true, false, false, false, Some(Side::Server)).unwrap();
+
+	let archive = path.join("export").join("pack-1.0.0-server-curseforge.zip");
+	assert_eq!(
+		zip_files(&archive),
+		vec![
+			"manifest.json",
+			"overrides/config/jei.toml",
+			"overrides/server.properties",
+		]
+	);
+	assert_eq!(read_from_zip(&archive, "overrides/config/jei.toml"), "both");
+}
+
+#[test]
+fn modpackr_exports_use_the_configured_globs() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = project_with_overrides(&server);
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.overrides.include = vec!["config/*".to_owned(), "*.properties".to_owned()];
+	config.overrides.exclude = Vec::new();
+	update_config(&path, &config).unwrap();
+
+	export(&path, false, false, true, false, None).unwrap();
+
+	let files = zip_files(&path.join("export").join("pack-1.0.0.modpackr"))
+		.into_iter()
+		.filter(|name| name.contains("overrides/"))
+		.collect::<Vec<_>>();
+	assert_eq!(
+		files,
+		vec![
+			"client-overrides/config/jei.toml",
+			"overrides/config/jei.toml",
+			"server-overrides/server.properties",
+		]
+	);
+
+	assert_eq!(load_config(&path).unwrap().overrides, config.overrides);
+}
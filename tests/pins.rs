@@ -0,0 +1,173 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader,
+		provider::Pin,
+		util::{check, export, lock, pin_mod},
+	},
+};
+
+const SODIUM_ALPHA: &str = "sodium-fabric-mc1.20.1-0.5.4-alpha.jar";
+
+#[test]
+fn pins_match_versions_and_files() {
+	let version: Pin = "0.5.3".parse().unwrap();
+	assert_eq!(version, Pin::Version("0.5.3".to_owned()));
+	assert!(version.matches("modrinth", "abc", &["mc1.20.1-0.5.3"], &[]));
+	assert!(!version.matches("modrinth", "abc", &["mc1.20.1-0.5.30"], &[]));
+
+	let wildcard = Pin::Version("0.5.*".to_owned());
+	assert!(wildcard.matches("curseforge", "1", &["create-1.20.1-0.5.1f.jar"], &[]));
+	assert!(!wildcard.matches("curseforge", "1", &["create-1.20.1-0.6.0.jar"], &[]));
+
+	let file: Pin = "curseforge:4712866".parse().unwrap();
+	assert_eq!(file.to_string(), "curseforge:4712866");
+	assert!(file.matches("curseforge", "4712866", &[], &[]));
+	assert!(!file.matches("modrinth", "4712866", &[], &[]));
+}
+
+#[test]
+fn version_pins_skip_the_minecraft_version() {
+	let game_versions = ["1.20.1".to_owned(), "Fabric".to_owned()];
+	let matches = |pattern: &str, name: &str| {
+		Pin::Version(pattern.to_owned()).matches("modrinth", "abc", &[name], &game_versions)
+	};
+
+	assert!(!matches("1.*", "mc1.20.1-0.5.3"));
+	assert!(!matches("1.20.1", "create-1.20.1-0.5.1f.jar"));
+	assert!(matches("0.5.*", "mc1.20.1-0.5.3"));
+	// A mod can still be pinned to a version that looks like a minecraft one
+	assert!(matches("1.*", "jei-1.20.1-1.0.2"));
+	// Patterns with a separator are matched against the whole name
+	assert!(matches("mc1.20.1-0.5.3", "mc1.20.1-0.5.3"));
+	assert!(!matches("mc1.20.1-0.5.3", "mc1.20.1-0.5.30"));
+}
+
+#[test]
+fn check_only_considers_versions_the_pin_allows() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	pin_mod(&path, "Sodium", Some(Pin::Version("0.5.3".to_owned()))).unwrap();
+
+	let result = check(&path).unwrap();
+
+	// Unpinned, sodium 0.5.8 makes this 1.20.4
+	assert_eq!(
+		result.target,
+		Some((ModLoader::Fabric, "1.20.1".to_owned()))
+	);
+}
+
+#[test]
+fn check_follows_a_pinned_curseforge_file() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+	pin_mod(
+		&path,
+		"Just Enough Items",
+		Some("curseforge:4712868".parse().unwrap()),
+	)
+	.unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(result.target, Some((ModLoader::Forge, "1.20.1".to_owned())));
+}
+
+#[test]
+fn curseforge_files_can_be_pinned_past_the_first_page() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Fabric API", 306612, "fabric-api")],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.19.2");
+	pin_mod(
+		&path,
+		"Fabric API",
+		Some("curseforge:4003701".parse().unwrap()),
+	)
+	.unwrap();
+
+	let lockfile = lock(&path).unwrap();
+
+	let file = lockfile
+		.get("Fabric API")
+		.unwrap()
+		.file("curseforge")
+		.unwrap();
+	assert_eq!(file.file_name, "fabric-api-0.76.1+1.19.2.jar");
+}
+
+#[test]
+fn lock_uses_the_pinned_file_and_relocks_when_the_pin_changes() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	let unpinned = lock(&path).unwrap();
+	let file = unpinned.get("Sodium").unwrap().file("modrinth").unwrap();
+	assert_eq!(file.file_name, "sodium-fabric-mc1.20.1-0.5.3.jar");
+
+	// Pins don't care about stability, so this picks the alpha
+	pin_mod(
+		&path,
+		"Sodium",
+		Some("modrinth:sodium-0.5.4-alpha-1.20.1".parse().unwrap()),
+	)
+	.unwrap();
+	let pinned = lock(&path).unwrap();
+	let sodium = pinned.get("Sodium").unwrap();
+	assert_eq!(sodium.files[0].file_name, SODIUM_ALPHA);
+	assert!(sodium.pin.is_some());
+
+	pin_mod(&path, "Sodium", None).unwrap();
+	assert_eq!(lock(&path).unwrap(), unpinned);
+}
+
+#[test]
+fn exports_ship_the_pinned_file() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	pin_mod(&path, "Sodium", Some(Pin::Version("0.5.4".to_owned()))).unwrap();
+
+	export(&path, false, true, false, false, None).unwrap();
+
+	let index = read_json_from_zip(
+		&path.join("export").join("pack-1.0.0.mrpack"),
+		"modrinth.index.json",
+	);
+	assert_eq!(index["files"][0]["path"], format!("mods/{SODIUM_ALPHA}"));
+}
+
+#[test]
+fn files_can_only_be_pinned_on_the_mods_providers() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+
+	let error = pin_mod(
+		&project_path(&dir),
+		"Sodium",
+		Some("curseforge:4712866".parse().unwrap()),
+	)
+	.unwrap_err();
+
+	assert_eq!(error.to_string(), "Sodium is not on curseforge");
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Endpoints, ModLoader,
+		server::{Launch, server_launcher},
+		util::{export, lock, update_lockfile},
+		version::MinecraftVersion,
+	},
+	std::{collections::BTreeMap, fs, path::Path},
+};
+
+/// Hashes of the jars in tests/fixtures/files
+const FILES: [(&str, &str); 2] = [
+	(
+		"fabric-api-0.92.0+1.20.1.jar",
+		"7f78e71ca7a397e36b3f874139c34b60c3def42d",
+	),
+	(
+		"jei-1.20.1-fabric-15.2.0.27.jar",
+		"43f45ea684902cb1207d47b03b859c7d945f2ffa",
+	),
+];
+
+/// Locks the pack and points every file at its copy on the fixture server, the recorded URLs are on the real CDNs
+fn lock_with_fixture_downloads(server: &FixtureServer, path: &Path) {
+	let mut lockfile = lock(path).unwrap();
+
+	for file in lockfile.mods.iter_mut().flat_map(|m| m.files.iter_mut()) {
+		file.url = Some(format!("{}/files/{}", server.base_url, file.file_name));
+		file.hashes = FILES
+			.iter()
+			.find(|(name, _)| *name == file.file_name)
+			.map(|(_, sha1)| BTreeMap::from([("sha1".to_owned(), sha1.to_string())]))
+			.unwrap_or_default();
+	}
+
+	update_lockfile(path, &lockfile).unwrap();
+}
+
+#[test]
+fn server_packs_have_everything_a_server_needs() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	fs::create_dir_all(path.join("overrides").join("config")).unwrap();
+	fs::write(path.join("overrides").join("config").join("jei.toml"), "a").unwrap();
+	fs::create_dir_all(path.join("server-overrides")).unwrap();
+	fs::write(path.join("server-overrides").join("server.properties"), "b").unwrap();
+	lock_with_fixture_downloads(&server, &path);
+
+	export(&path, false, false, false, true, None).unwrap();
+
+	let server_dir = path.join("export").join("pack-1.0.0-server");
+	let mut mods = fs::read_dir(server_dir.join("mods"))
+		.unwrap()
+		.map(|entry| entry.unwrap().file_name().into_string().unwrap())
+		.collect::<Vec<_>>();
+	mods.sort();
+	// Sodium is client only
+	assert_eq!(mods, vec![FILES[0].0, FILES[1].0]);
+	assert_eq!(
+		fs::read_to_string(server_dir.join("mods").join(FILES[1].0)).unwrap(),
+		"jei jar\n"
+	);
+
+	assert!(server_dir.join("config").join("jei.toml").is_file());
+	assert!(server_dir.join("server.properties").is_file());
+	assert_eq!(
+		fs::read_to_string(server_dir.join("fabric-server-launch.jar")).unwrap(),
+		"fabric server launcher\n"
+	);
+	assert!(
+		fs::read_to_string(server_dir.join("eula.txt"))
+			.unwrap()
+			.contains("eula=false")
+	);
+
+	let start_sh = fs::read_to_string(server_dir.join("start.sh")).unwrap();
+	assert!(start_sh.contains("needs Java 17"), "{start_sh}");
+	assert!(
+		server
+			.requests()
+			.contains(&"/mojang/v1/packages/0000/1.20.1.json".to_owned())
+	);
+	assert!(start_sh.contains("-jar fabric-server-launch.jar nogui"));
+	let start_bat = fs::read_to_string(server_dir.join("start.bat")).unwrap();
+	assert!(start_bat.contains("-jar fabric-server-launch.jar nogui"));
+
+	assert!(path.join("export").join("pack-1.0.0-server.zip").is_file());
+}
+
+#[test]
+fn downloads_that_dont_match_their_hash_fail_the_export() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+	lock_with_fixture_downloads(&server, &path);
+
+	let mut lockfile = lock(&path).unwrap();
+	lockfile.mods[0].files[0]
+		.hashes
+		.insert("sha1".to_owned(), "0".repeat(40));
+	update_lockfile(&path, &lockfile).unwrap();
+
+	let error = export(&path, false, false, false, true, None)
+		.unwrap_err()
+		.to_string();
+
+	assert!(error.contains("doesn't match its sha1 hash"), "{error}");
+}
+
+#[test]
+fn forge_servers_are_installed_on_their_first_start() {
+	let launcher = server_launcher(
+		&Endpoints::default(),
+		&ModLoader::Forge,
+		"1.20.1",
+		"forge-47.2.0",
+	)
+	.unwrap();
+
+	assert_eq!(
+		launcher.url,
+		"https://maven.minecraftforge.net/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-installer.jar"
+	);
+	assert_eq!(
+		launcher.launch,
+		Launch::ArgsFile("libraries/net/minecraftforge/forge/1.20.1-47.2.0".to_owned())
+	);
+
+	let start_sh = launcher.start_sh("1.20.1", 17);
+	assert!(
+		start_sh
+			.contains("if [ ! -f libraries/net/minecraftforge/forge/1.20.1-47.2.0/unix_args.txt ]")
+	);
+	assert!(start_sh.contains("-jar forge-1.20.1-47.2.0-installer.jar --installServer"));
+	assert!(
+		start_sh.contains("@libraries/net/minecraftforge/forge/1.20.1-47.2.0/unix_args.txt nogui")
+	);
+	assert!(
+		launcher
+			.start_bat("1.20.1", 17)
+			.contains("/win_args.txt nogui")
+	);
+}
+
+#[test]
+fn neoforge_on_1_20_1_uses_the_old_forge_artifact() {
+	let launcher = server_launcher(
+		&Endpoints::default(),
+		&ModLoader::Neoforge,
+		"1.20.1",
+		"neoforge-47.1.106",
+	)
+	.unwrap();
+
+	assert_eq!(
+		launcher.url,
+		"https://maven.neoforged.net/releases/net/neoforged/forge/1.20.1-47.1.106/forge-1.20.1-47.1.106-installer.jar"
+	);
+
+	let launcher = server_launcher(
+		&Endpoints::default(),
+		&ModLoader::Neoforge,
+		"1.21.1",
+		"neoforge-21.1.77",
+	)
+	.unwrap();
+	assert_eq!(
+		launcher.url,
+		"https://maven.neoforged.net/releases/net/neoforged/neoforge/21.1.77/neoforge-21.1.77-installer.jar"
+	);
+	assert!(launcher.start_sh("1.21.1", 21).contains("needs Java 21"));
+}
+
+#[test]
+fn java_versions_follow_the_minecraft_version() {
+	let java = |version: &str| MinecraftVersion::new(version).java_version();
+
+	assert_eq!(java("1.12.2"), 8);
+	assert_eq!(java("1.16.5"), 8);
+	assert_eq!(java("1.17.1"), 16);
+	assert_eq!(java("1.18.2"), 17);
+	assert_eq!(java("1.20.4"), 17);
+	assert_eq!(java("1.20.5"), 21);
+	assert_eq!(java("1.21-pre1"), 21);
+	assert_eq!(java("23w40a"), 17);
+	assert_eq!(java("24w14a"), 21);
+	assert_eq!(java("1.21.11"), 21);
+	assert_eq!(java("26.1"), 25);
+	assert_eq!(java("26.1-snapshot-1"), 25);
+	assert_eq!(java("26.2-rc-1"), 25);
+}
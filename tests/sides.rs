@@ -0,0 +1,173 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		ModLoader, Side, Sides, Support,
+		curseforge::CurseforgeModFile,
+		util::{edit_mod, export, lock, update_lockfile},
+	},
+};
+
+const CLIENT_ONLY: Sides = Sides {
+	client: Support::Required,
+	server: Support::Unsupported,
+};
+
+#[test]
+fn sides_describe_where_a_mod_runs() {
+	assert_eq!(Sides::default().to_string(), "both sides");
+	assert_eq!(CLIENT_ONLY.to_string(), "client only");
+	assert!(CLIENT_ONLY.supports(Side::Client));
+	assert!(!CLIENT_ONLY.supports(Side::Server));
+
+	let optional = Sides {
+		client: Support::Required,
+		server: Support::Optional,
+	};
+	assert_eq!(optional.to_string(), "client required, server optional");
+	assert!(optional.supports(Side::Server));
+
+	assert_eq!("server".parse::<Side>().unwrap(), Side::Server);
+	assert!("both".parse::<Side>().is_err());
+}
+
+#[test]
+fn lock_records_the_sides_providers_report() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	let lockfile = lock(&path).unwrap();
+
+	// Modrinth says sodium is client side only, curseforge tags JEI with both Client and Server, which says nothing
+	assert_eq!(lockfile.get("Sodium").unwrap().sides(), Some(CLIENT_ONLY));
+	assert_eq!(
+		lockfile.get("Fabric API").unwrap().sides(),
+		Some(Sides::default())
+	);
+	assert_eq!(lockfile.get("Just Enough Items").unwrap().sides(), None);
+}
+
+#[test]
+fn locked_files_without_sides_get_them_on_the_next_lock() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	// Like a lockfile from before sides were recorded
+	let mut lockfile = lock(&path).unwrap();
+	for locked in lockfile.mods.iter_mut() {
+		for file in locked.files.iter_mut() {
+			file.sides = None;
+		}
+	}
+	update_lockfile(&path, &lockfile).unwrap();
+
+	let relocked = lock(&path).unwrap();
+
+	assert_eq!(relocked.get("Sodium").unwrap().sides(), Some(CLIENT_ONLY));
+	assert_eq!(
+		relocked.get("Sodium").unwrap().files[0].file_id,
+		lockfile.get("Sodium").unwrap().files[0].file_id
+	);
+}
+
+#[test]
+fn server_exports_leave_out_client_only_mods() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	export(&path, false, true, false, false, Some(Side::Server)).unwrap();
+	export(&path, false, true, false, false, Some(Side::Client)).unwrap();
+
+	let paths = |name: &str| {
+		read_json_from_zip(&path.join("export").join(name), "modrinth.index.json")["files"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|file| file["path"].as_str().unwrap().to_owned())
+			.collect::<Vec<_>>()
+	};
+
+	assert_eq!(
+		paths("pack-1.0.0-server.mrpack"),
+		vec!["mods/fabric-api-0.92.0+1.20.1.jar"]
+	);
+	assert_eq!(paths("pack-1.0.0-client.mrpack").len(), 2);
+}
+
+#[test]
+fn overrides_win_over_what_providers_say() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+	set_target(&path, ModLoader::Fabric, "1.20.1");
+
+	edit_mod(&path, "Sodium", |m| m.server = Some(Support::Optional)).unwrap();
+	export(&path, false, true, false, false, Some(Side::Server)).unwrap();
+
+	let index = read_json_from_zip(
+		&path.join("export").join("pack-1.0.0-server.mrpack"),
+		"modrinth.index.json",
+	);
+	let sodium = &index["files"][0];
+	assert_eq!(sodium["env"]["client"], "required");
+	assert_eq!(sodium["env"]["server"], "optional");
+}
+
+#[test]
+fn curseforge_tags_only_count_for_server_only_files() {
+	let sides = |game_versions: &[&str]| {
+		serde_json::from_value::<CurseforgeModFile>(serde_json::json!({
+			"id": 1,
+			"fileName": "a.jar",
+			"releaseType": 1,
+			"fileDate": "2024-01-01T00:00:00Z",
+			"gameVersions": game_versions,
+			"downloadUrl": null,
+		}))
+		.unwrap()
+		.sides()
+	};
+
+	assert_eq!(
+		sides(&["Fabric", "1.20.1", "Server"]),
+		Some(Sides {
+			client: Support::Unsupported,
+			server: Support::Required,
+		})
+	);
+	// Lots of mods for both sides are only tagged with Client
+	assert_eq!(sides(&["Fabric", "1.20.1", "Client"]), None);
+	assert_eq!(sides(&["Fabric", "1.20.1", "Client", "Server"]), None);
+	assert_eq!(sides(&["Fabric", "1.20.1"]), None);
+}
+
+#[test]
+fn modpackr_exports_cant_be_split_by_side() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, default_mods());
+
+	let error = export(
+		&project_path(&dir),
+		false,
+		false,
+		true,
+		false,
+		Some(Side::Client),
+	)
+	.unwrap_err()
+	.to_string();
+
+	assert!(error.contains("can't be exported for one side"), "{error}");
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Config, ModLoader, ModVersions, Strategy,
+		mojang::VersionCatalogue,
+		util::{check, compatible_targets, load_config, update_config},
+		version::MinecraftVersion,
+	},
+};
+
+fn versions(fabric: &[&str], forge: &[&str]) -> ModVersions {
+	ModVersions {
+		fabric: fabric.iter().map(|v| MinecraftVersion::new(*v)).collect(),
+		forge: forge.iter().map(|v| MinecraftVersion::new(*v)).collect(),
+		..Default::default()
+	}
+}
+
+// Without bridges, so quilt and neoforge don't pick up the fabric and forge versions
+fn config() -> Config {
+	Config {
+		bridges: Vec::new(),
+		..Default::default()
+	}
+}
+
+fn target(loader: ModLoader, version: &str) -> (ModLoader, String) {
+	(loader, version.to_owned())
+}
+
+// Fabric is the default first choice, but forge supports a newer version
+fn mods() -> [ModVersions; 2] {
+	[
+		versions(&["1.20.1", "1.20.4"], &["1.20.1", "1.21"]),
+		versions(&["1.20.1"], &["1.20.1", "1.21"]),
+	]
+}
+
+#[test]
+fn loader_first_lists_every_target_by_loader() {
+	assert_eq!(
+		compatible_targets(&mods(), &config(), &VersionCatalogue::default()),
+		vec![
+			target(ModLoader::Fabric, "1.20.1"),
+			target(ModLoader::Forge, "1.21"),
+			target(ModLoader::Forge, "1.20.1"),
+		]
+	);
+}
+
+#[test]
+fn loader_order_changes_the_preferred_loader() {
+	let config = Config {
+		loader_order: vec![ModLoader::Forge],
+		..config()
+	};
+
+	assert_eq!(config.loaders()[..2], [ModLoader::Forge, ModLoader::Fabric]);
+	assert_eq!(
+		compatible_targets(&mods(), &config, &VersionCatalogue::default())[0],
+		target(ModLoader::Forge, "1.21")
+	);
+}
+
+#[test]
+fn newest_first_prefers_the_newest_version_on_any_loader() {
+	let config = Config {
+		strategy: Strategy::NewestFirst,
+		..config()
+	};
+
+	assert_eq!(
+		compatible_targets(&mods(), &config, &VersionCatalogue::default()),
+		vec![
+			target(ModLoader::Forge, "1.21"),
+			target(ModLoader::Fabric, "1.20.1"),
+			target(ModLoader::Forge, "1.20.1"),
+		]
+	);
+}
+
+#[test]
+fn check_reports_every_compatible_target() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(&server, vec![modrinth_mod("Sodium", "AANobbMI", "sodium")]);
+	let path = project_path(&dir);
+
+	let mut config = load_config(&path).unwrap();
+	config.loader_order = vec![ModLoader::Quilt];
+	update_config(&path, &config).unwrap();
+
+	let result = check(&path).unwrap();
+
+	assert_eq!(result.target, Some(target(ModLoader::Quilt, "1.20.4")));
+	assert_eq!(result.compatible[0], target(ModLoader::Quilt, "1.20.4"));
+	assert!(
+		result
+			.compatible
+			.contains(&target(ModLoader::Fabric, "1.20.4"))
+	);
+}
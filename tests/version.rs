@@ -0,0 +1,266 @@
+mod common;
+
+use {
+	common::*,
+	modpackr::{
+		Config, ModLoader, ModVersions,
+		mojang::{VersionCatalogue, VersionManifest, VersionType},
+		util::{check, find_compatible},
+		version::{MinecraftVersion, Stage},
+	},
+	std::{collections::BTreeSet, fs, fs::File},
+};
+
+fn versions(list: &[&str]) -> BTreeSet<MinecraftVersion> {
+	list.iter().map(|v| MinecraftVersion::new(*v)).collect()
+}
+
+fn fabric(list: &[&str]) -> ModVersions {
+	ModVersions {
+		fabric: versions(list),
+		..Default::default()
+	}
+}
+
+#[test]
+fn versions_sort_in_release_order() {
+	let sorted = versions(&[
+		"1.21",
+		"1.9",
+		"1.21-rc1",
+		"24w14a",
+		"1.20.4",
+		"1.21-pre2",
+		"1.21-pre1",
+		"1.20.10",
+		"23w51b",
+		"1.21-snapshot",
+		"1.20.5",
+		"24w18a",
+	])
+	.into_iter()
+	.map(|v| v.to_string())
+	.collect::<Vec<_>>();
+
+	assert_eq!(
+		sorted,
+		vec![
+			"1.9",
+			"1.20.4",
+			"23w51b",
+			"24w14a",
+			"1.20.5",
+			"1.20.10",
+			"1.21-snapshot",
+			"24w18a",
+			"1.21-pre1",
+			"1.21-pre2",
+			"1.21-rc1",
+			"1.21",
+		]
+	);
+
+	// Snapshots for a release that isn't out yet come after every release
+	assert!(MinecraftVersion::new("26w02a") > MinecraftVersion::new("1.21.11"));
+
+	// Since 26.1 versions are numbered by year
+	assert_eq!(
+		versions(&[
+			"26.1",
+			"26.1-rc-1",
+			"26.1-snapshot-2",
+			"26.1-pre-1",
+			"26.1-snapshot-10",
+			"1.21.11"
+		])
+		.into_iter()
+		.map(|v| v.to_string())
+		.collect::<Vec<_>>(),
+		vec![
+			"1.21.11",
+			"26.1-snapshot-2",
+			"26.1-snapshot-10",
+			"26.1-pre-1",
+			"26.1-rc-1",
+			"26.1",
+		]
+	);
+}
+
+#[test]
+fn versions_are_classified() {
+	assert!(MinecraftVersion::new("1.20.1").is_release());
+	assert!(!MinecraftVersion::new("1.21-pre1").is_release());
+	assert!(!MinecraftVersion::new("24w14a").is_release());
+
+	assert_eq!(
+		MinecraftVersion::new("1.14 Pre-Release 2").stage(),
+		Some(Stage::PreRelease(2))
+	);
+	assert_eq!(
+		MinecraftVersion::new("1.21-rc1").stage(),
+		Some(Stage::ReleaseCandidate(1))
+	);
+	assert_eq!(
+		MinecraftVersion::new("24w14a").stage(),
+		Some(Stage::Snapshot)
+	);
+
+	assert_eq!(
+		MinecraftVersion::new("26.1-snapshot-1").stage(),
+		Some(Stage::Snapshot)
+	);
+	assert_eq!(
+		MinecraftVersion::new("26.1-pre-2").stage(),
+		Some(Stage::PreRelease(2))
+	);
+
+	assert!(!MinecraftVersion::new("Java 17").is_known());
+	assert!(!MinecraftVersion::new("b1.7.3").is_known());
+}
+
+#[test]
+fn find_compatible_compares_versions_numerically() {
+	let result = find_compatible(
+		&[fabric(&["1.9", "1.21"])],
+		&Config::default(),
+		&VersionCatalogue::default(),
+	);
+
+	assert_eq!(result, Some((ModLoader::Fabric, "1.21".to_owned())));
+}
+
+#[test]
+fn find_compatible_skips_snapshots_unless_allowed() {
+	let mods = [
+		fabric(&["1.20.4", "1.21-pre1", "24w14a"]),
+		fabric(&["1.20.4", "1.21-pre1", "24w14a"]),
+	];
+
+	assert_eq!(
+		find_compatible(&mods, &Config::default(), &VersionCatalogue::default()),
+		Some((ModLoader::Fabric, "1.20.4".to_owned()))
+	);
+	assert_eq!(
+		find_compatible(
+			&mods,
+			&Config {
+				allow_snapshots: true,
+				..Default::default()
+			},
+			&VersionCatalogue::default(),
+		),
+		Some((ModLoader::Fabric, "1.21-pre1".to_owned()))
+	);
+	assert_eq!(
+		find_compatible(
+			&mods,
+			&Config {
+				version: Some("24w14a".to_owned()),
+				..Default::default()
+			},
+			&VersionCatalogue::default(),
+		),
+		Some((ModLoader::Fabric, "24w14a".to_owned()))
+	);
+}
+
+#[test]
+fn catalogue_normalizes_provider_versions() {
+	let manifest: VersionManifest = serde_json::from_reader(
+		File::open(
+			fixtures_dir()
+				.join("mojang")
+				.join("mc")
+				.join("game")
+				.join("version_manifest_v2.json"),
+		)
+		.unwrap(),
+	)
+	.unwrap();
+	let catalogue = VersionCatalogue::from(manifest);
+
+	assert!(catalogue.is_release("1.20.1"));
+	assert!(!catalogue.is_release("24w14a"));
+	assert_eq!(
+		catalogue.get("1.21-pre1").unwrap().version_type,
+		VersionType::Snapshot
+	);
+
+	let normalized = catalogue.normalize(fabric(&[
+		"1.20.1",
+		"1.20-snapshot",
+		"java 17",
+		"1.14 pre-release 1",
+		"client",
+	]));
+
+	assert_eq!(
+		normalized.fabric,
+		versions(&["1.20.1", "1.14 Pre-Release 1"])
+	);
+}
+
+#[test]
+fn the_catalogue_reads_java_versions_from_mojang() {
+	let server = FixtureServer::start();
+	let catalogue = VersionCatalogue::fetch(&format!("{}/mojang", server.base_url)).unwrap();
+
+	assert_eq!(catalogue.java_version("1.20.1").unwrap(), 17);
+	assert!(
+		server
+			.requests()
+			.contains(&"/mojang/v1/packages/0000/1.20.1.json".to_owned())
+	);
+	// Versions mojang hasn't published go by their name
+	assert_eq!(catalogue.java_version("26.1").unwrap(), 25);
+}
+
+#[test]
+fn the_catalogue_orders_versions_by_release_time() {
+	let manifest: VersionManifest = serde_json::from_value(serde_json::json!({
+		"latest": { "release": "1.20", "snapshot": "1.20" },
+		"versions": [
+			{ "id": "1.20", "type": "release", "releaseTime": "2023-06-07T09:35:19+00:00" },
+			{ "id": "23w13a_or_b", "type": "snapshot", "releaseTime": "2023-04-01T12:00:00+00:00" },
+			{ "id": "1.19.4", "type": "release", "releaseTime": "2023-03-14T12:56:18+00:00" },
+		],
+	}))
+	.unwrap();
+	let catalogue = VersionCatalogue::from(manifest);
+	let config = Config {
+		allow_snapshots: true,
+		..Default::default()
+	};
+	let mods = [fabric(&["1.19.4", "23w13a_or_b"])];
+
+	// The april fools snapshot doesn't look like a version, but mojang released it after 1.19.4
+	assert!(!catalogue.is_release("23w13a_or_b"));
+	assert_eq!(
+		find_compatible(&mods, &config, &catalogue),
+		Some((ModLoader::Fabric, "23w13a_or_b".to_owned()))
+	);
+	assert_eq!(
+		find_compatible(&mods, &config, &VersionCatalogue::default()),
+		Some((ModLoader::Fabric, "1.19.4".to_owned()))
+	);
+}
+
+#[test]
+fn check_ignores_versions_mojang_never_released() {
+	let _guard = lock_env();
+	let server = FixtureServer::start();
+	let dir = create_project(
+		&server,
+		vec![curseforge_mod("Just Enough Items", 238222, "jei")],
+	);
+	let path = project_path(&dir);
+
+	check(&path).unwrap();
+
+	let versions: ModVersions = ron::from_str(
+		&fs::read_to_string(path.join("mods").join("Just Enough Items.ron")).unwrap(),
+	)
+	.unwrap();
+	assert_eq!(versions.forge, self::versions(&["1.20.1"]));
+}